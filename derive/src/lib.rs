@@ -0,0 +1,333 @@
+//! Derive macros for the `FromJson` and `ToJson` traits of `nom-json-parser`.
+//!
+//! The macros are re-exported by `nom-json-parser` when its `derive` feature is enabled. See the
+//! documentation of the traits for the supported attributes.
+
+extern crate proc_macro;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, ExprPath, Field, Fields,
+    GenericParam, Generics, Ident, Index, Lifetime, LifetimeParam, LitStr, Result, Token,
+};
+
+/// Derives `FromJson` for a struct, or an enum with unit variants only.
+#[proc_macro_derive(FromJson, attributes(json))]
+pub fn derive_from_json(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_json(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derives `ToJson` for a struct, or an enum with unit variants only.
+#[proc_macro_derive(ToJson, attributes(json))]
+pub fn derive_to_json(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_to_json(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// The `#[json(...)]` attributes of a field or variant.
+#[derive(Default)]
+struct Attrs {
+    rename: Option<String>,
+    /// `Some(None)` for `#[json(default)]`, and `Some(Some(path))` for `#[json(default = "path")]`.
+    default: Option<Option<ExprPath>>,
+    flatten: bool,
+}
+
+impl Attrs {
+    fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut parsed = Attrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("json")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    parsed.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("default") {
+                    parsed.default = Some(match meta.input.peek(Token![=]) {
+                        true => Some(meta.value()?.parse::<LitStr>()?.parse()?),
+                        false => None,
+                    });
+                } else if meta.path.is_ident("flatten") {
+                    parsed.flatten = true;
+                } else {
+                    return Err(meta.error("unknown json attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(parsed)
+    }
+
+    fn for_field(field: &Field) -> Result<Self> {
+        let attrs = Self::parse(&field.attrs)?;
+        if attrs.flatten && (attrs.rename.is_some() || attrs.default.is_some()) {
+            return Err(Error::new(
+                field.span(),
+                "`flatten` cannot be combined with `rename` or `default`",
+            ));
+        }
+        Ok(attrs)
+    }
+
+    fn for_variant(variant: &syn::Variant) -> Result<Self> {
+        let attrs = Self::parse(&variant.attrs)?;
+        if attrs.flatten || attrs.default.is_some() {
+            return Err(Error::new(
+                variant.span(),
+                "only `rename` is supported on enum variants",
+            ));
+        }
+        Ok(attrs)
+    }
+}
+
+/// Returns the lifetime of the strings to convert from: the first lifetime of the type, or a new
+/// one added to `generics`.
+fn json_lifetime(generics: &mut Generics) -> Lifetime {
+    if let Some(lifetime) = generics.lifetimes().next() {
+        return lifetime.lifetime.clone();
+    }
+    let lifetime = Lifetime::new("'__json", Span::call_site());
+    generics.params.insert(
+        0,
+        GenericParam::Lifetime(LifetimeParam::new(lifetime.clone())),
+    );
+    lifetime
+}
+
+/// Adds `bound` to every type parameter of `generics`.
+fn add_bounds(generics: &mut Generics, bound: TokenStream) {
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+}
+
+fn expand_from_json(input: &DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    let lifetime = json_lifetime(&mut generics);
+    add_bounds(
+        &mut generics,
+        quote!(::nom_json_parser::FromJson<#lifetime>),
+    );
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => from_json_struct(name, &data.fields)?,
+        Data::Enum(data) => {
+            let arms = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    unit_variant(variant)?;
+                    let key = Attrs::for_variant(variant)?
+                        .rename
+                        .unwrap_or_else(|| variant.ident.to_string());
+                    let ident = &variant.ident;
+                    Ok(quote!(::std::option::Option::Some(#key) => ::std::result::Result::Ok(#name::#ident),))
+                })
+                .collect::<Result<TokenStream>>()?;
+            quote! {
+                match json.as_str() {
+                    #arms
+                    ::std::option::Option::Some(other) => ::std::result::Result::Err(
+                        ::nom_json_parser::ConversionError::new(
+                            ::nom_json_parser::ConversionErrorKind::UnknownVariant(other.to_string()),
+                        ),
+                    ),
+                    ::std::option::Option::None => ::std::result::Result::Err(
+                        ::nom_json_parser::ConversionError::invalid_type("a string", json),
+                    ),
+                }
+            }
+        }
+        Data::Union(_) => return Err(Error::new(input.span(), "unions are not supported")),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::nom_json_parser::FromJson<#lifetime> for #name #ty_generics #where_clause {
+            fn from_json(
+                json: &::nom_json_parser::Json<#lifetime>,
+            ) -> ::std::result::Result<Self, ::nom_json_parser::ConversionError> {
+                #body
+            }
+        }
+    })
+}
+
+fn from_json_struct(name: &Ident, fields: &Fields) -> Result<TokenStream> {
+    let convert = quote!(::nom_json_parser::FromJson::from_json);
+    match fields {
+        Fields::Named(fields) => {
+            let inits = fields
+                .named
+                .iter()
+                .map(|field| {
+                    let attrs = Attrs::for_field(field)?;
+                    let ident = field.ident.as_ref().unwrap();
+                    if attrs.flatten {
+                        return Ok(quote!(#ident: #convert(json)?,));
+                    }
+                    let key = attrs.rename.unwrap_or_else(|| unraw(ident));
+                    let missing = match attrs.default {
+                        Some(Some(path)) => quote!(#path()),
+                        Some(None) => quote!(::std::default::Default::default()),
+                        None => quote! {
+                            #convert(&::nom_json_parser::Json::from(::std::option::Option::None))
+                                .map_err(|_| {
+                                    ::nom_json_parser::ConversionError::new(
+                                        ::nom_json_parser::ConversionErrorKind::MissingField,
+                                    )
+                                    .in_key(#key)
+                                })?
+                        },
+                    };
+                    Ok(quote! {
+                        #ident: match obj.get(#key) {
+                            ::std::option::Option::Some(value) => {
+                                #convert(value).map_err(|err| err.in_key(#key))?
+                            }
+                            ::std::option::Option::None => #missing,
+                        },
+                    })
+                })
+                .collect::<Result<TokenStream>>()?;
+            Ok(quote! {
+                let obj = match json.as_object() {
+                    ::std::option::Option::Some(obj) => obj,
+                    ::std::option::Option::None => {
+                        return ::std::result::Result::Err(
+                            ::nom_json_parser::ConversionError::invalid_type("an object", json),
+                        )
+                    }
+                };
+                ::std::result::Result::Ok(#name { #inits })
+            })
+        }
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+            Ok(quote!(::std::result::Result::Ok(#name(#convert(json)?))))
+        }
+        Fields::Unnamed(fields) => {
+            let len = fields.unnamed.len();
+            let elems =
+                (0..len).map(|i| quote!(#convert(&arr[#i]).map_err(|err| err.in_index(#i))?));
+            Ok(quote! {
+                let arr = match json.as_array() {
+                    ::std::option::Option::Some(arr) => arr,
+                    ::std::option::Option::None => {
+                        return ::std::result::Result::Err(
+                            ::nom_json_parser::ConversionError::invalid_type("an array", json),
+                        )
+                    }
+                };
+                if arr.len() != #len {
+                    return ::std::result::Result::Err(::nom_json_parser::ConversionError::new(
+                        ::nom_json_parser::ConversionErrorKind::InvalidLength {
+                            expected: #len,
+                            found: arr.len(),
+                        },
+                    ));
+                }
+                ::std::result::Result::Ok(#name(#(#elems),*))
+            })
+        }
+        Fields::Unit => Ok(quote!(#convert(json).map(|()| #name))),
+    }
+}
+
+fn expand_to_json(input: &DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    add_bounds(&mut generics, quote!(::nom_json_parser::ToJson));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => to_json_struct(&data.fields)?,
+        Data::Enum(data) => {
+            let arms = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    unit_variant(variant)?;
+                    let key = Attrs::for_variant(variant)?
+                        .rename
+                        .unwrap_or_else(|| variant.ident.to_string());
+                    let ident = &variant.ident;
+                    Ok(quote!(#name::#ident => ::nom_json_parser::Json::from(#key),))
+                })
+                .collect::<Result<TokenStream>>()?;
+            quote!(match *self { #arms })
+        }
+        Data::Union(_) => return Err(Error::new(input.span(), "unions are not supported")),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::nom_json_parser::ToJson for #name #ty_generics #where_clause {
+            fn to_json(&self) -> ::nom_json_parser::Json<'_> {
+                #body
+            }
+        }
+    })
+}
+
+fn to_json_struct(fields: &Fields) -> Result<TokenStream> {
+    let convert = quote!(::nom_json_parser::ToJson::to_json);
+    match fields {
+        Fields::Named(fields) => {
+            let inserts = fields
+                .named
+                .iter()
+                .map(|field| {
+                    let attrs = Attrs::for_field(field)?;
+                    let ident = field.ident.as_ref().unwrap();
+                    if attrs.flatten {
+                        return Ok(quote! {
+                            if let ::std::option::Option::Some(members) = #convert(&self.#ident).as_object() {
+                                obj.extend(members.iter().map(|(key, value)| (key.clone(), value.clone())));
+                            }
+                        });
+                    }
+                    let key = attrs.rename.unwrap_or_else(|| unraw(ident));
+                    Ok(quote!(obj.insert(#key, #convert(&self.#ident));))
+                })
+                .collect::<Result<TokenStream>>()?;
+            Ok(quote! {
+                let mut obj = ::nom_json_parser::Map::new();
+                #inserts
+                ::nom_json_parser::Json::from(obj)
+            })
+        }
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => Ok(quote!(#convert(&self.0))),
+        Fields::Unnamed(fields) => {
+            let elems = (0..fields.unnamed.len()).map(Index::from);
+            Ok(quote! {
+                ::nom_json_parser::Json::from(vec![#(#convert(&self.#elems)),*])
+            })
+        }
+        Fields::Unit => Ok(quote!(::nom_json_parser::Json::from(
+            ::std::option::Option::None
+        ))),
+    }
+}
+
+fn unit_variant(variant: &syn::Variant) -> Result<()> {
+    match variant.fields {
+        Fields::Unit => Ok(()),
+        _ => Err(Error::new(
+            variant.fields.span(),
+            "only enums with unit variants are supported",
+        )),
+    }
+}
+
+/// Returns the name of a field without the `r#` prefix of raw identifiers.
+fn unraw(ident: &Ident) -> String {
+    let name = ident.to_token_stream().to_string();
+    name.trim_start_matches("r#").to_string()
+}
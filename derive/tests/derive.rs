@@ -0,0 +1,123 @@
+use nom_json_parser::{json, parse_complete, ConversionErrorKind, FromJson, JsonPointer, ToJson};
+use std::borrow::Cow;
+
+#[derive(FromJson, ToJson, PartialEq, Debug)]
+struct Person<'a> {
+    name: &'a str,
+    #[json(rename = "yearsOld")]
+    age: u8,
+    #[json(default)]
+    tags: Vec<String>,
+    #[json(default = "default_score")]
+    score: f64,
+    nickname: Option<Cow<'a, str>>,
+    #[json(flatten)]
+    address: Address,
+}
+
+fn default_score() -> f64 {
+    1.0
+}
+
+#[derive(FromJson, ToJson, PartialEq, Debug)]
+struct Address {
+    city: String,
+    zip: Option<u32>,
+}
+
+#[derive(FromJson, ToJson, PartialEq, Eq, Debug)]
+enum Color {
+    Red,
+    #[json(rename = "green")]
+    Green,
+}
+
+#[derive(FromJson, ToJson, PartialEq, Debug)]
+struct Meters(f64);
+
+#[derive(FromJson, ToJson, PartialEq, Debug)]
+struct Point(i32, i32, Color);
+
+#[derive(FromJson, ToJson, PartialEq, Debug)]
+struct Unit;
+
+#[derive(FromJson, ToJson, PartialEq, Debug)]
+struct Wrapper<T> {
+    items: Vec<T>,
+}
+
+#[test]
+fn test_struct() {
+    let source = r#"{"name": "Alice", "yearsOld": 30, "city": "Paris", "extra": true}"#;
+    let json = parse_complete(source).unwrap();
+    let person = Person::from_json(&json).unwrap();
+    assert_eq!(
+        person,
+        Person {
+            name: "Alice",
+            age: 30,
+            tags: vec![],
+            score: 1.0,
+            nickname: None,
+            address: Address {
+                city: "Paris".to_string(),
+                zip: None,
+            },
+        }
+    );
+    assert_eq!(
+        person.to_json().to_string(),
+        r#"{"name": "Alice", "yearsOld": 30, "tags": [], "score": 1.0, "nickname": null, "city": "Paris", "zip": null}"#
+    );
+    assert_eq!(Person::from_json(&person.to_json()).unwrap(), person);
+}
+
+#[test]
+fn test_errors() {
+    let json = json!({"name": "Bob", "city": "Rome"});
+    let err = Person::from_json(&json).unwrap_err();
+    assert_eq!(err.kind(), &ConversionErrorKind::MissingField);
+    assert_eq!(err.path(), &"/yearsOld".parse::<JsonPointer>().unwrap());
+
+    let json = json!({"items": [{"city": "Rome"}, {"city": 1}]});
+    let err = Wrapper::<Address>::from_json(&json).unwrap_err();
+    assert_eq!(err.path(), &"/items/1/city".parse::<JsonPointer>().unwrap());
+    assert_eq!(
+        err.to_string(),
+        r#"invalid type: expected a string, found a number at "/items/1/city""#
+    );
+
+    let json = json!([1, 2, "blue"]);
+    let err = Point::from_json(&json).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ConversionErrorKind::UnknownVariant("blue".to_string())
+    );
+    assert_eq!(err.path(), &"/2".parse::<JsonPointer>().unwrap());
+    let err = Point::from_json(&json!([1, 2])).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ConversionErrorKind::InvalidLength {
+            expected: 3,
+            found: 2
+        }
+    );
+    assert!(Unit::from_json(&json!(false)).is_err());
+}
+
+#[test]
+fn test_other_shapes() {
+    assert_eq!(Color::from_json(&json!("green")).unwrap(), Color::Green);
+    assert_eq!(Color::Red.to_json(), json!("Red"));
+    assert_eq!(Meters::from_json(&json!(2.5)).unwrap(), Meters(2.5));
+    assert_eq!(Meters(2.5).to_json(), json!(2.5));
+    let point = Point(1, -2, Color::Red);
+    assert_eq!(point.to_json(), json!([1, -2, "Red"]));
+    assert_eq!(Point::from_json(&point.to_json()).unwrap(), point);
+    assert_eq!(Unit::from_json(&json!(null)).unwrap(), Unit);
+    assert_eq!(Unit.to_json(), json!(null));
+    let wrapper = Wrapper {
+        items: vec![Color::Green],
+    };
+    assert_eq!(wrapper.to_json(), json!({"items": ["green"]}));
+}
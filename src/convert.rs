@@ -0,0 +1,633 @@
+use crate::map::Map;
+use crate::pointer::JsonPointer;
+use crate::repr::{Json, JsonValue};
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt::{Display, Error, Formatter};
+use std::hash::{BuildHasher, Hash};
+use std::iter;
+
+/// Conversion from a [`Json`](struct.Json.html) value, the counterpart of
+/// [`ToJson`](trait.ToJson.html).
+///
+/// The lifetime `'a` is the lifetime of the strings in the JSON value, so implementations can
+/// borrow from the parsed input. `&'a str` only converts from strings that are borrowed, that is,
+/// strings without escape sequences in the source. `Cow<'a, str>` accepts any string and borrows
+/// it when possible.
+///
+/// Errors carry the path to the value that failed to convert. Implementations for containers
+/// extend it with [`ConversionError::in_key`](struct.ConversionError.html#method.in_key) and
+/// [`ConversionError::in_index`](struct.ConversionError.html#method.in_index).
+///
+/// With the `derive` feature, `#[derive(FromJson)]` implements this trait for structs and enums
+/// with unit variants. Members are matched by field name, and the derive understands these
+/// field attributes:
+///
+/// * `#[json(rename = "name")]` reads the field from the member `name`.
+/// * `#[json(default)]` uses `Default::default()` when the member is missing, and
+///   `#[json(default = "path")]` calls the function `path` instead.
+/// * `#[json(flatten)]` converts the field from the whole object, which lets it read members of
+///   its own.
+///
+/// Other missing members convert from `null`, so `Option` fields become `None`, and any other
+/// field fails with `ConversionErrorKind::MissingField`. Unknown members are ignored.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{parse_complete, ConversionErrorKind, FromJson};
+/// use std::collections::HashMap;
+///
+/// let json = parse_complete(r#"{"alice": [1, 2], "bob": [3, "x"]}"#).unwrap();
+/// let err = HashMap::<&str, Vec<u8>>::from_json(&json).unwrap_err();
+/// assert_eq!(err.path().to_string(), "/bob/1");
+/// assert_eq!(err.to_string(), "invalid type: expected an integer, found a string at \"/bob/1\"");
+///
+/// let json = parse_complete(r#"[["a\tb", 1], [null, 2]]"#).unwrap();
+/// let pairs = Vec::<(Option<String>, i8)>::from_json(&json).unwrap();
+/// assert_eq!(pairs, vec![(Some("a\tb".to_string()), 1), (None, 2)]);
+/// assert_eq!(
+///     <&str>::from_json(&json[0][0]).unwrap_err().kind(),
+///     &ConversionErrorKind::NotBorrowed
+/// );
+/// ```
+pub trait FromJson<'a>: Sized {
+    /// Converts a JSON value into `Self`.
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError>;
+}
+
+/// Conversion into a [`Json`](struct.Json.html) value, the counterpart of
+/// [`FromJson`](trait.FromJson.html).
+///
+/// Strings are borrowed from `self`. Call [`Json::into_owned`](struct.Json.html#method.into_owned)
+/// on the result to keep it longer. Non-finite floats become `null`.
+///
+/// With the `derive` feature, `#[derive(ToJson)]` implements this trait and understands the same
+/// attributes as `#[derive(FromJson)]`. A flattened field adds the members of its object to the
+/// enclosing one, and is skipped if it is not an object.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::ToJson;
+/// use std::collections::BTreeMap;
+///
+/// let mut scores = BTreeMap::new();
+/// scores.insert("alice", (Some(1.5), vec![true]));
+/// scores.insert("bob", (None, vec![]));
+/// assert_eq!(
+///     scores.to_json().to_string(),
+///     r#"{"alice": [1.5, [true]], "bob": [null, []]}"#
+/// );
+/// ```
+pub trait ToJson {
+    /// Converts `self` into a JSON value.
+    fn to_json(&self) -> Json<'_>;
+}
+
+/// An error from [`FromJson`](trait.FromJson.html), with the path to the value that failed to
+/// convert.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ConversionError {
+    path: JsonPointer,
+    kind: ConversionErrorKind,
+}
+
+/// The kind of a [`ConversionError`](struct.ConversionError.html).
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ConversionErrorKind {
+    /// The value has the wrong type, such as a string where a number was expected.
+    InvalidType {
+        /// A description of the expected value, such as "an integer".
+        expected: &'static str,
+        /// A description of the actual value, such as "a string".
+        found: &'static str,
+    },
+    /// A number does not fit into the named Rust type.
+    OutOfRange(&'static str),
+    /// An array converted into a tuple has the wrong number of elements.
+    InvalidLength {
+        /// The length of the tuple.
+        expected: usize,
+        /// The length of the array.
+        found: usize,
+    },
+    /// An object has no member for a required field.
+    MissingField,
+    /// A string does not name a variant of an enum.
+    UnknownVariant(String),
+    /// A string cannot be borrowed as `&str`, because it contained escape sequences.
+    NotBorrowed,
+    /// An error reported by a `FromJson` implementation.
+    Custom(String),
+}
+
+impl ConversionError {
+    /// Creates an error at the root of the value being converted.
+    pub fn new(kind: ConversionErrorKind) -> Self {
+        ConversionError {
+            path: JsonPointer::new(),
+            kind,
+        }
+    }
+
+    /// Creates an `InvalidType` error for `found`.
+    pub fn invalid_type(expected: &'static str, found: &Json<'_>) -> Self {
+        let found = match found.as_value() {
+            None => "null",
+            Some(JsonValue::Bool(_)) => "a boolean",
+            Some(JsonValue::Int(_))
+            | Some(JsonValue::UInt(_))
+            | Some(JsonValue::Float(_))
+            | Some(JsonValue::Number(_)) => "a number",
+            Some(JsonValue::String(_)) => "a string",
+            Some(JsonValue::Array(_)) => "an array",
+            Some(JsonValue::Object(_)) => "an object",
+        };
+        Self::new(ConversionErrorKind::InvalidType { expected, found })
+    }
+
+    /// Creates a `Custom` error with the given message.
+    pub fn custom<T: Display>(msg: T) -> Self {
+        Self::new(ConversionErrorKind::Custom(msg.to_string()))
+    }
+
+    /// Returns the path to the value that failed to convert.
+    pub fn path(&self) -> &JsonPointer {
+        &self.path
+    }
+
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> &ConversionErrorKind {
+        &self.kind
+    }
+
+    /// Records that the error occurred inside the value of the object member `key`.
+    pub fn in_key(self, key: &str) -> Self {
+        self.prepend(key.to_string())
+    }
+
+    /// Records that the error occurred inside the array element at `index`.
+    pub fn in_index(self, index: usize) -> Self {
+        self.prepend(index.to_string())
+    }
+
+    fn prepend(mut self, segment: String) -> Self {
+        self.path = iter::once(segment)
+            .chain(self.path.segments().iter().cloned())
+            .collect();
+        self
+    }
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match &self.kind {
+            ConversionErrorKind::InvalidType { expected, found } => {
+                write!(f, "invalid type: expected {}, found {}", expected, found)?
+            }
+            ConversionErrorKind::OutOfRange(ty) => write!(f, "number out of range for {}", ty)?,
+            ConversionErrorKind::InvalidLength { expected, found } => write!(
+                f,
+                "invalid length: expected {} elements, found {}",
+                expected, found
+            )?,
+            ConversionErrorKind::MissingField => f.write_str("missing field")?,
+            ConversionErrorKind::UnknownVariant(variant) => {
+                write!(f, "unknown variant {:?}", variant)?
+            }
+            ConversionErrorKind::NotBorrowed => {
+                f.write_str("cannot borrow a string with escape sequences")?
+            }
+            ConversionErrorKind::Custom(msg) => f.write_str(msg)?,
+        };
+        write!(f, " at \"{}\"", self.path)
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+macro_rules! impl_int {
+    ($($ty:ty),*) => {
+        $(
+            impl<'a> FromJson<'a> for $ty {
+                fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+                    let out_of_range =
+                        || ConversionError::new(ConversionErrorKind::OutOfRange(stringify!($ty)));
+                    match json.as_value() {
+                        Some(JsonValue::Number(number)) if number.is_integer() => {
+                            number.as_i128()
+                                .and_then(|int| <$ty>::try_from(int).ok())
+                                .ok_or_else(out_of_range)
+                        }
+                        Some(JsonValue::Int(int)) => <$ty>::try_from(*int).map_err(|_| out_of_range()),
+                        Some(JsonValue::UInt(uint)) => <$ty>::try_from(*uint).map_err(|_| out_of_range()),
+                        _ => Err(ConversionError::invalid_type("an integer", json)),
+                    }
+                }
+            }
+
+            impl ToJson for $ty {
+                fn to_json(&self) -> Json<'_> {
+                    (*self).into()
+                }
+            }
+        )*
+    };
+}
+
+impl_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl<'a> FromJson<'a> for f64 {
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        match json.as_value() {
+            Some(JsonValue::Number(_)) => json
+                .as_f64()
+                .ok_or_else(|| ConversionError::new(ConversionErrorKind::OutOfRange("f64"))),
+            _ => json
+                .as_f64()
+                .ok_or_else(|| ConversionError::invalid_type("a number", json)),
+        }
+    }
+}
+
+impl<'a> FromJson<'a> for f32 {
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        f64::from_json(json).map(|float| float as f32)
+    }
+}
+
+impl ToJson for f64 {
+    fn to_json(&self) -> Json<'_> {
+        if self.is_finite() {
+            (*self).into()
+        } else {
+            Json(None)
+        }
+    }
+}
+
+impl ToJson for f32 {
+    fn to_json(&self) -> Json<'_> {
+        if self.is_finite() {
+            f64::from(*self).into()
+        } else {
+            Json(None)
+        }
+    }
+}
+
+impl<'a> FromJson<'a> for bool {
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        json.as_bool()
+            .ok_or_else(|| ConversionError::invalid_type("a boolean", json))
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> Json<'_> {
+        (*self).into()
+    }
+}
+
+impl<'a> FromJson<'a> for () {
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        match json.as_value() {
+            None => Ok(()),
+            Some(_) => Err(ConversionError::invalid_type("null", json)),
+        }
+    }
+}
+
+impl ToJson for () {
+    fn to_json(&self) -> Json<'_> {
+        Json(None)
+    }
+}
+
+impl<'a> FromJson<'a> for &'a str {
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        match json.as_value() {
+            Some(JsonValue::String(Cow::Borrowed(string))) => Ok(string),
+            Some(JsonValue::String(Cow::Owned(_))) => {
+                Err(ConversionError::new(ConversionErrorKind::NotBorrowed))
+            }
+            _ => Err(ConversionError::invalid_type("a string", json)),
+        }
+    }
+}
+
+impl<'a> FromJson<'a> for Cow<'a, str> {
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        match json.as_value() {
+            Some(JsonValue::String(string)) => Ok(string.clone()),
+            _ => Err(ConversionError::invalid_type("a string", json)),
+        }
+    }
+}
+
+impl<'a> FromJson<'a> for String {
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        json.as_str()
+            .map(String::from)
+            .ok_or_else(|| ConversionError::invalid_type("a string", json))
+    }
+}
+
+impl<'a> FromJson<'a> for char {
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        let mut chars = json.as_str().unwrap_or_default().chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(ConversionError::invalid_type("a single character", json)),
+        }
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> Json<'_> {
+        self.into()
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Json<'_> {
+        self.as_str().into()
+    }
+}
+
+impl ToJson for Cow<'_, str> {
+    fn to_json(&self) -> Json<'_> {
+        self.as_ref().into()
+    }
+}
+
+impl ToJson for char {
+    fn to_json(&self) -> Json<'_> {
+        self.to_string().into()
+    }
+}
+
+impl<'a> FromJson<'a> for Json<'a> {
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        Ok(json.clone())
+    }
+}
+
+impl ToJson for Json<'_> {
+    fn to_json(&self) -> Json<'_> {
+        self.borrow()
+    }
+}
+
+impl<'a, T: FromJson<'a>> FromJson<'a> for Option<T> {
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        match json.as_value() {
+            None => Ok(None),
+            Some(_) => T::from_json(json).map(Some),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Json<'_> {
+        match self {
+            Some(value) => value.to_json(),
+            None => Json(None),
+        }
+    }
+}
+
+impl<'a, T: FromJson<'a>> FromJson<'a> for Box<T> {
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        T::from_json(json).map(Box::new)
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn to_json(&self) -> Json<'_> {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> Json<'_> {
+        (**self).to_json()
+    }
+}
+
+impl<'a, T: FromJson<'a>> FromJson<'a> for Vec<T> {
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        json.as_array()
+            .ok_or_else(|| ConversionError::invalid_type("an array", json))?
+            .iter()
+            .enumerate()
+            .map(|(i, elem)| T::from_json(elem).map_err(|err| err.in_index(i)))
+            .collect()
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> Json<'_> {
+        JsonValue::Array(self.iter().map(T::to_json).collect()).into()
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Json<'_> {
+        self.as_slice().to_json()
+    }
+}
+
+/// Converts the members of an object, with keys converted from JSON strings.
+fn from_members<'a, K: FromJson<'a>, V: FromJson<'a>, C: iter::FromIterator<(K, V)>>(
+    json: &Json<'a>,
+) -> Result<C, ConversionError> {
+    json.as_object()
+        .ok_or_else(|| ConversionError::invalid_type("an object", json))?
+        .iter()
+        .map(|(key, value)| {
+            let convert = || Ok((K::from_json(&key.clone().into())?, V::from_json(value)?));
+            convert().map_err(|err: ConversionError| err.in_key(key))
+        })
+        .collect()
+}
+
+fn to_members<'a, K: AsRef<str> + 'a, V: ToJson + 'a>(
+    members: impl Iterator<Item = (&'a K, &'a V)>,
+) -> Json<'a> {
+    members
+        .map(|(key, value)| (key.as_ref(), value.to_json()))
+        .collect::<Map>()
+        .into()
+}
+
+impl<'a, K, V, S> FromJson<'a> for HashMap<K, V, S>
+where
+    K: FromJson<'a> + Eq + Hash,
+    V: FromJson<'a>,
+    S: BuildHasher + Default,
+{
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        from_members(json)
+    }
+}
+
+impl<K: AsRef<str>, V: ToJson, S> ToJson for HashMap<K, V, S> {
+    fn to_json(&self) -> Json<'_> {
+        to_members(self.iter())
+    }
+}
+
+impl<'a, K: FromJson<'a> + Ord, V: FromJson<'a>> FromJson<'a> for BTreeMap<K, V> {
+    fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+        from_members(json)
+    }
+}
+
+impl<K: AsRef<str>, V: ToJson> ToJson for BTreeMap<K, V> {
+    fn to_json(&self) -> Json<'_> {
+        to_members(self.iter())
+    }
+}
+
+macro_rules! impl_tuple {
+    ($len:expr => $($name:ident $index:tt),+) => {
+        impl<'a, $($name: FromJson<'a>),+> FromJson<'a> for ($($name,)+) {
+            fn from_json(json: &Json<'a>) -> Result<Self, ConversionError> {
+                let arr = json
+                    .as_array()
+                    .ok_or_else(|| ConversionError::invalid_type("an array", json))?;
+                if arr.len() != $len {
+                    return Err(ConversionError::new(ConversionErrorKind::InvalidLength {
+                        expected: $len,
+                        found: arr.len(),
+                    }));
+                }
+                Ok(($($name::from_json(&arr[$index]).map_err(|err| err.in_index($index))?,)+))
+            }
+        }
+
+        impl<$($name: ToJson),+> ToJson for ($($name,)+) {
+            fn to_json(&self) -> Json<'_> {
+                JsonValue::Array(vec![$(self.$index.to_json()),+]).into()
+            }
+        }
+    };
+}
+
+impl_tuple!(1 => A 0);
+impl_tuple!(2 => A 0, B 1);
+impl_tuple!(3 => A 0, B 1, C 2);
+impl_tuple!(4 => A 0, B 1, C 2, D 3);
+impl_tuple!(5 => A 0, B 1, C 2, D 3, E 4);
+impl_tuple!(6 => A 0, B 1, C 2, D 3, E 4, F 5);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::options::ParserOptions;
+    use crate::parser::parse_complete;
+
+    fn convert<'a, T: FromJson<'a>>(json: &Json<'a>) -> Result<T, ConversionError> {
+        T::from_json(json)
+    }
+
+    #[test]
+    fn test_numbers() {
+        let json = parse_complete("[-1, 255, 256, 18446744073709551615, 1.5, 1e400]").unwrap();
+        assert_eq!(convert::<i8>(&json[0]), Ok(-1));
+        assert_eq!(convert::<u8>(&json[1]), Ok(255));
+        assert_eq!(
+            convert::<u8>(&json[2]).unwrap_err().kind(),
+            &ConversionErrorKind::OutOfRange("u8")
+        );
+        assert_eq!(convert::<u64>(&json[3]), Ok(u64::MAX));
+        assert!(convert::<i64>(&json[3]).is_err());
+        assert_eq!(convert::<f32>(&json[4]), Ok(1.5));
+        assert_eq!(
+            convert::<i32>(&json[4]).unwrap_err().to_string(),
+            "invalid type: expected an integer, found a number at \"\""
+        );
+
+        let parser = ParserOptions::new().lossless_numbers(true).build();
+        let json = parser.parse_complete("[-128, -129, 1.0, 1e400]").unwrap();
+        assert_eq!(convert::<i8>(&json[0]), Ok(-128));
+        assert!(convert::<i8>(&json[1]).is_err());
+        assert_eq!(convert::<f64>(&json[2]), Ok(1.0));
+        assert_eq!(
+            convert::<f64>(&json[3]).unwrap_err().kind(),
+            &ConversionErrorKind::OutOfRange("f64")
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        let json = parse_complete(r#"["plain", "esc\n", "é", 1]"#).unwrap();
+        assert_eq!(convert::<&str>(&json[0]), Ok("plain"));
+        assert!(matches!(
+            convert::<Cow<str>>(&json[0]),
+            Ok(Cow::Borrowed("plain"))
+        ));
+        assert!(matches!(convert::<Cow<str>>(&json[1]), Ok(Cow::Owned(_))));
+        assert_eq!(convert::<String>(&json[1]), Ok("esc\n".to_string()));
+        assert_eq!(convert::<char>(&json[2]), Ok('é'));
+        assert!(convert::<char>(&json[0]).is_err());
+        assert_eq!(
+            convert::<String>(&json[3]).unwrap_err().kind(),
+            &ConversionErrorKind::InvalidType {
+                expected: "a string",
+                found: "a number"
+            }
+        );
+    }
+
+    #[test]
+    fn test_containers() {
+        let json = parse_complete(r#"{"a": [[1, "x"]], "b": [[2, null]], "c": []}"#).unwrap();
+        let map: BTreeMap<String, Vec<(u8, Option<&str>)>> = convert(&json).unwrap();
+        assert_eq!(map["a"], [(1, Some("x"))]);
+        assert_eq!(map["b"], [(2, None)]);
+        assert_eq!(map.to_json(), json);
+
+        let map: HashMap<&str, Json> = convert(&json).unwrap();
+        assert_eq!(map["c"], Json::from(Vec::<Json>::new()));
+
+        let err = convert::<HashMap<String, Vec<(u8, u8)>>>(&json).unwrap_err();
+        assert_eq!(err.path().to_string(), "/a/0/1");
+        let err = convert::<HashMap<String, Vec<(u8,)>>>(&json).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &ConversionErrorKind::InvalidLength {
+                expected: 1,
+                found: 2
+            }
+        );
+        assert_eq!(err.path().to_string(), "/a/0");
+    }
+
+    #[test]
+    fn test_to_json() {
+        let value = (
+            vec![Some(1u8), None],
+            "tab\t".to_string(),
+            'c',
+            (f64::NAN, 0.5f32),
+            Box::new(()),
+        );
+        assert_eq!(
+            value.to_json().to_string(),
+            r#"[[1, null], "tab\t", "c", [null, 0.5], null]"#
+        );
+        assert_eq!(
+            convert::<(Vec<Option<u8>>, String, char, (Option<f64>, f32), ())>(&value.to_json()),
+            Ok((
+                vec![Some(1), None],
+                "tab\t".to_string(),
+                'c',
+                (None, 0.5),
+                ()
+            ))
+        );
+    }
+}
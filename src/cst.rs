@@ -0,0 +1,1115 @@
+use crate::map::Map;
+use crate::options::{DuplicateKeys, ParserOptions};
+use crate::parser::{Lexeme, Member, Members, Syntax};
+use crate::pointer::{index, ErrorFn, JsonPointer, PointerError};
+use crate::repr::{Json, JsonValue};
+use crate::utils::escape;
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt::{Display, Error, Formatter};
+use std::mem;
+
+/// A lossless concrete syntax tree of a JSON document, created by
+/// [`parse_cst`](fn.parse_cst.html) or [`Parser::parse_cst`](struct.Parser.html#method.parse_cst).
+///
+/// Every token of the document is kept with the whitespace and comments around it, so `Display`
+/// prints the document back exactly as it was parsed. The tree can be edited in place with JSON
+/// Pointers, and edits leave the rest of the document untouched, including its comments.
+///
+/// Whitespace and comments following a token up to the end of its line are the trailing trivia
+/// of the token, and the rest are the leading trivia of the next token. Removing a value removes
+/// the comments on its lines, and new values copy the indentation of their siblings. New values
+/// are written on a single line as by the `Display` impl of `Json`, or by
+/// [`Json::json5`](struct.Json.html#method.json5) in a tree parsed as JSON5.
+///
+/// The tree keeps every member of an object with repeated keys, and resolves them with the
+/// `duplicate_keys` option it was parsed with, as the parsed `Json` would. With
+/// `DuplicateKeys::Collect`, a repeated key is followed in pointers by the index of one of its
+/// members, as in the collected array.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{json, ParserOptions};
+///
+/// let input = r#"{
+///     // The port to listen on.
+///     "port": 8080,
+///     "hosts": ["a", "b"] // primary first
+/// }
+/// "#;
+/// let parser = ParserOptions::new().allow_comments(true).build();
+/// let mut cst = parser.parse_cst(input).unwrap();
+/// assert_eq!(cst.to_string(), input);
+///
+/// cst.set("/port", 9090).unwrap();
+/// cst.insert("/hosts/-", "c").unwrap();
+/// cst.insert("/debug", json!({"level": 2})).unwrap();
+/// assert_eq!(
+///     cst.to_string(),
+///     r#"{
+///     // The port to listen on.
+///     "port": 9090,
+///     "hosts": ["a", "b", "c"], // primary first
+///     "debug": {"level": 2}
+/// }
+/// "#
+/// );
+///
+/// cst.remove("/hosts").unwrap();
+/// assert_eq!(cst.to_json(), json!({"port": 9090, "debug": {"level": 2}}));
+/// ```
+#[derive(PartialEq, Clone, Debug)]
+pub struct Cst<'a> {
+    pub(crate) root: CstValue<'a>,
+    pub(crate) end: Cow<'a, str>,
+    pub(crate) duplicate_keys: DuplicateKeys,
+    pub(crate) json5: bool,
+}
+
+/// A token with its leading and trailing whitespace and comments.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Token<'a> {
+    pub(crate) leading: Cow<'a, str>,
+    pub(crate) text: Cow<'a, str>,
+    pub(crate) trailing: Cow<'a, str>,
+}
+
+/// A value in a [`Cst`](struct.Cst.html).
+#[derive(PartialEq, Clone, Debug)]
+pub enum CstValue<'a> {
+    /// `null`, a boolean, a number or a string, along with its value.
+    Scalar(Token<'a>, Json<'a>),
+    /// An array.
+    Array(CstArray<'a>),
+    /// An object.
+    Object(CstObject<'a>),
+}
+
+/// An array in a [`Cst`](struct.Cst.html).
+#[derive(PartialEq, Clone, Debug)]
+pub struct CstArray<'a> {
+    pub(crate) open: Token<'a>,
+    pub(crate) elements: Vec<CstElement<'a>>,
+    pub(crate) close: Token<'a>,
+}
+
+/// An array element in a [`Cst`](struct.Cst.html), with the comma following it.
+#[derive(PartialEq, Clone, Debug)]
+pub struct CstElement<'a> {
+    pub(crate) value: CstValue<'a>,
+    pub(crate) comma: Option<Token<'a>>,
+}
+
+/// An object in a [`Cst`](struct.Cst.html).
+#[derive(PartialEq, Clone, Debug)]
+pub struct CstObject<'a> {
+    pub(crate) open: Token<'a>,
+    pub(crate) members: Vec<CstMember<'a>>,
+    pub(crate) close: Token<'a>,
+}
+
+/// An object member in a [`Cst`](struct.Cst.html), with the comma following it.
+#[derive(PartialEq, Clone, Debug)]
+pub struct CstMember<'a> {
+    pub(crate) key: Token<'a>,
+    pub(crate) name: Cow<'a, str>,
+    pub(crate) colon: Token<'a>,
+    pub(crate) value: CstValue<'a>,
+    pub(crate) comma: Option<Token<'a>>,
+}
+
+impl<'a> Cst<'a> {
+    pub(crate) fn new(root: CstValue<'a>, end: &'a str, options: &ParserOptions) -> Self {
+        Cst {
+            root,
+            end: end.into(),
+            duplicate_keys: options.duplicate_keys,
+            json5: options.json5,
+        }
+    }
+
+    /// Returns the root value of the document.
+    pub fn root(&self) -> &CstValue<'a> {
+        &self.root
+    }
+
+    /// Returns the whitespace and comments after the last line of the root value.
+    pub fn end(&self) -> &str {
+        &self.end
+    }
+
+    /// Converts the document to a `Json` value, as parsed with the same options.
+    pub fn to_json(&self) -> Json<'a> {
+        self.root.to_json(self.duplicate_keys)
+    }
+
+    /// Returns the value at the given JSON Pointer.
+    pub fn get(&self, pointer: &str) -> Result<&CstValue<'a>, PointerError> {
+        let pointer = pointer.parse::<JsonPointer>()?;
+        let segments = pointer.segments();
+        let mut value = &self.root;
+        let mut i = 0;
+        while i < segments.len() {
+            let (child, next) = child(value, segments, i, self.duplicate_keys)?;
+            value = child;
+            i = next;
+        }
+        Ok(value)
+    }
+
+    /// Replaces the value at the given JSON Pointer, which must exist. The trivia around the old
+    /// value is kept.
+    pub fn set<V: Into<Json<'a>>>(&mut self, pointer: &str, value: V) -> Result<(), PointerError> {
+        let pointer = pointer.parse::<JsonPointer>()?;
+        let old = get_mut(&mut self.root, pointer.segments(), self.duplicate_keys)?;
+        old.replace(CstValue::from_json(value.into(), self.json5));
+        Ok(())
+    }
+
+    /// Adds a value at the given JSON Pointer, with the semantics of
+    /// [`JsonPointer::insert`](struct.JsonPointer.html#method.insert). An existing member is
+    /// replaced in place, and a new member is appended to its object. Members are not added to
+    /// or replaced in the values collected for a repeated key.
+    pub fn insert<V: Into<Json<'a>>>(
+        &mut self,
+        pointer: &str,
+        value: V,
+    ) -> Result<(), PointerError> {
+        let pointer = pointer.parse::<JsonPointer>()?;
+        let segments = pointer.segments();
+        let value = CstValue::from_json(value.into(), self.json5);
+        let last = match segments.last() {
+            Some(last) => last,
+            None => {
+                self.root.replace(value);
+                return Ok(());
+            }
+        };
+        let policy = self.duplicate_keys;
+        match get_mut(&mut self.root, &segments[..segments.len() - 1], policy)? {
+            CstValue::Object(obj) => match find_member(obj, segments, segments.len() - 1, policy) {
+                Ok((i, _)) => obj.members[i].value.replace(value),
+                Err(PointerError::MissingKey(_)) => {
+                    let member = CstMember::new(last.clone(), value);
+                    let len = obj.members.len();
+                    insert_item(&mut obj.members, len, member);
+                }
+                Err(err) => return Err(err),
+            },
+            CstValue::Array(arr) => {
+                let index = match last.as_str() {
+                    "-" => arr.elements.len(),
+                    _ => index(last).map_err(|err| err(pointer.clone()))?,
+                };
+                if index > arr.elements.len() {
+                    return Err(PointerError::IndexOutOfBounds(pointer));
+                }
+                let element = CstElement { value, comma: None };
+                insert_item(&mut arr.elements, index, element);
+            }
+            CstValue::Scalar(..) => return Err(PointerError::NotAContainer(pointer)),
+        }
+        Ok(())
+    }
+
+    /// Removes the value at the given JSON Pointer, along with its comma and the trivia on its
+    /// lines. The empty pointer replaces the document with `null`. A repeated key removes all of
+    /// its members, and the index of a member of a collected key removes only that member.
+    pub fn remove(&mut self, pointer: &str) -> Result<(), PointerError> {
+        let pointer = pointer.parse::<JsonPointer>()?;
+        let segments = pointer.segments();
+        let last = match segments.last() {
+            Some(last) => last,
+            None => {
+                self.root
+                    .replace(CstValue::from_json(Json(None), self.json5));
+                return Ok(());
+            }
+        };
+        let policy = self.duplicate_keys;
+        let (parent, i) = parent_mut(&mut self.root, segments, policy)?;
+        match parent {
+            CstValue::Object(obj) => match find_member(obj, segments, i, policy) {
+                Ok((j, next)) if next > i + 1 => remove_item(&mut obj.members, j),
+                Ok(_) | Err(PointerError::Collected(_)) => {
+                    let name = &segments[i];
+                    while let Some(j) = obj.members.iter().rposition(|m| m.name == *name) {
+                        remove_item(&mut obj.members, j);
+                    }
+                }
+                Err(err) => return Err(err),
+            },
+            CstValue::Array(arr) => match index(last) {
+                Ok(i) if i < arr.elements.len() => remove_item(&mut arr.elements, i),
+                Ok(_) => return Err(PointerError::IndexOutOfBounds(pointer)),
+                Err(err) => return Err(err(pointer)),
+            },
+            CstValue::Scalar(..) => return Err(PointerError::NotAContainer(pointer)),
+        }
+        Ok(())
+    }
+}
+
+fn prefix(segments: &[String], len: usize) -> JsonPointer {
+    segments[..len].iter().cloned().collect()
+}
+
+/// Finds the member of `obj` whose key is `segments[i]`, as selected by `policy`. Returns its
+/// index along with the index of the next segment, which follows the index of the member among
+/// the members of a collected key.
+fn find_member(
+    obj: &CstObject<'_>,
+    segments: &[String],
+    i: usize,
+    policy: DuplicateKeys,
+) -> Result<(usize, usize), PointerError> {
+    let name = &segments[i];
+    let mut positions = (0..obj.members.len()).filter(|&j| obj.members[j].name == *name);
+    let first = positions
+        .next()
+        .ok_or_else(|| PointerError::MissingKey(prefix(segments, i + 1)))?;
+    match policy {
+        DuplicateKeys::FirstWins => Ok((first, i + 1)),
+        DuplicateKeys::Collect if obj.members[first + 1..].iter().any(|m| m.name == *name) => {
+            let segment = segments
+                .get(i + 1)
+                .ok_or_else(|| PointerError::Collected(prefix(segments, i + 1)))?;
+            let n = index(segment).map_err(|err| err(prefix(segments, i + 2)))?;
+            let j = std::iter::once(first)
+                .chain(positions)
+                .nth(n)
+                .ok_or_else(|| PointerError::IndexOutOfBounds(prefix(segments, i + 2)))?;
+            Ok((j, i + 2))
+        }
+        _ => Ok((positions.next_back().unwrap_or(first), i + 1)),
+    }
+}
+
+/// Returns the value referred to by `segments[i]` in `value`, along with the index of the next
+/// segment.
+fn child<'c, 'a>(
+    value: &'c CstValue<'a>,
+    segments: &[String],
+    i: usize,
+    policy: DuplicateKeys,
+) -> Result<(&'c CstValue<'a>, usize), PointerError> {
+    let err = |err: ErrorFn| err(prefix(segments, i + 1));
+    match value {
+        CstValue::Object(obj) => {
+            let (j, next) = find_member(obj, segments, i, policy)?;
+            Ok((&obj.members[j].value, next))
+        }
+        CstValue::Array(arr) => arr
+            .elements
+            .get(index(&segments[i]).map_err(err)?)
+            .map(|element| (&element.value, i + 1))
+            .ok_or_else(|| err(PointerError::IndexOutOfBounds)),
+        CstValue::Scalar(..) => Err(err(PointerError::NotAContainer)),
+    }
+}
+
+fn child_mut<'c, 'a>(
+    value: &'c mut CstValue<'a>,
+    segments: &[String],
+    i: usize,
+    policy: DuplicateKeys,
+) -> Result<(&'c mut CstValue<'a>, usize), PointerError> {
+    let err = |err: ErrorFn| err(prefix(segments, i + 1));
+    match value {
+        CstValue::Object(obj) => {
+            let (j, next) = find_member(obj, segments, i, policy)?;
+            Ok((&mut obj.members[j].value, next))
+        }
+        CstValue::Array(arr) => arr
+            .elements
+            .get_mut(index(&segments[i]).map_err(err)?)
+            .map(|element| (&mut element.value, i + 1))
+            .ok_or_else(|| err(PointerError::IndexOutOfBounds)),
+        CstValue::Scalar(..) => Err(err(PointerError::NotAContainer)),
+    }
+}
+
+/// Resolves the container of the value that `segments` refer to. Returns it along with the index
+/// of the segment that refers to the value in it, which is followed by the index of a member of a
+/// collected key.
+fn parent_mut<'c, 'a>(
+    root: &'c mut CstValue<'a>,
+    segments: &[String],
+    policy: DuplicateKeys,
+) -> Result<(&'c mut CstValue<'a>, usize), PointerError> {
+    let mut value = root;
+    let mut i = 0;
+    loop {
+        let collected = match &*value {
+            CstValue::Object(obj) if policy == DuplicateKeys::Collect => {
+                let mut members = obj.members.iter().filter(|m| m.name == segments[i]);
+                members.nth(1).is_some()
+            }
+            _ => false,
+        };
+        if i + 1 + collected as usize >= segments.len() {
+            return Ok((value, i));
+        }
+        let (child, next) = child_mut(value, segments, i, policy)?;
+        value = child;
+        i = next;
+    }
+}
+
+fn get_mut<'c, 'a>(
+    root: &'c mut CstValue<'a>,
+    segments: &[String],
+    policy: DuplicateKeys,
+) -> Result<&'c mut CstValue<'a>, PointerError> {
+    let mut value = root;
+    let mut i = 0;
+    while i < segments.len() {
+        let (child, next) = child_mut(value, segments, i, policy)?;
+        value = child;
+        i = next;
+    }
+    Ok(value)
+}
+
+impl<'a> Token<'a> {
+    pub(crate) fn new<L, T, R>(leading: L, text: T, trailing: R) -> Self
+    where
+        L: Into<Cow<'a, str>>,
+        T: Into<Cow<'a, str>>,
+        R: Into<Cow<'a, str>>,
+    {
+        Token {
+            leading: leading.into(),
+            text: text.into(),
+            trailing: trailing.into(),
+        }
+    }
+
+    /// Returns the whitespace and comments before the token.
+    pub fn leading(&self) -> &str {
+        &self.leading
+    }
+
+    /// Returns the source text of the token.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the whitespace and comments after the token, up to the end of its line.
+    pub fn trailing(&self) -> &str {
+        &self.trailing
+    }
+}
+
+impl<'a> CstValue<'a> {
+    /// Converts the value to a `Json` value, resolving repeated keys with `duplicate_keys`.
+    /// `DuplicateKeys::Error` keeps the first member, as a parsed tree has no repeated keys then.
+    pub fn to_json(&self, duplicate_keys: DuplicateKeys) -> Json<'a> {
+        match self {
+            CstValue::Scalar(_, json) => json.clone(),
+            CstValue::Array(arr) => arr
+                .elements
+                .iter()
+                .map(|element| element.value.to_json(duplicate_keys))
+                .collect::<Vec<_>>()
+                .into(),
+            CstValue::Object(obj) => {
+                let mut members = Members::default();
+                for member in &obj.members {
+                    let value = member.value.to_json(duplicate_keys);
+                    members.insert(member.name.clone(), value, duplicate_keys);
+                }
+                Map(members.map).into()
+            }
+        }
+    }
+
+    /// Builds a value written on a single line, without trivia around it. Non-finite floats are
+    /// written as in JSON5 with `json5`, and are replaced with `null` otherwise.
+    fn from_json(json: Json<'a>, json5: bool) -> Self {
+        let comma = |i, len| match i + 1 < len {
+            true => Some(Token::new("", ",", " ")),
+            false => None,
+        };
+        match json.0 {
+            Some(JsonValue::Array(arr)) => {
+                let len = arr.len();
+                let elements = arr
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, value)| CstElement {
+                        value: CstValue::from_json(value, json5),
+                        comma: comma(i, len),
+                    })
+                    .collect();
+                CstValue::Array(CstArray {
+                    open: Token::new("", "[", ""),
+                    elements,
+                    close: Token::new("", "]", ""),
+                })
+            }
+            Some(JsonValue::Object(obj)) => {
+                let len = obj.len();
+                let members = obj
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (name, value))| CstMember {
+                        comma: comma(i, len),
+                        ..CstMember::new(name, CstValue::from_json(value, json5))
+                    })
+                    .collect();
+                CstValue::Object(CstObject {
+                    open: Token::new("", "{", ""),
+                    members,
+                    close: Token::new("", "}", ""),
+                })
+            }
+            Some(JsonValue::Float(float)) if !json5 && !float.is_finite() => {
+                CstValue::from_json(Json(None), json5)
+            }
+            json => {
+                let json = Json(json);
+                let text = match json5 {
+                    true => json.json5().to_string(),
+                    false => json.to_string(),
+                };
+                CstValue::Scalar(Token::new("", text, ""), json)
+            }
+        }
+    }
+
+    /// Returns the first token of the value.
+    pub fn first(&self) -> &Token<'a> {
+        match self {
+            CstValue::Scalar(token, _) => token,
+            CstValue::Array(arr) => &arr.open,
+            CstValue::Object(obj) => &obj.open,
+        }
+    }
+
+    /// Returns the last token of the value.
+    pub fn last(&self) -> &Token<'a> {
+        match self {
+            CstValue::Scalar(token, _) => token,
+            CstValue::Array(arr) => &arr.close,
+            CstValue::Object(obj) => &obj.close,
+        }
+    }
+
+    fn first_mut(&mut self) -> &mut Token<'a> {
+        match self {
+            CstValue::Scalar(token, _) => token,
+            CstValue::Array(arr) => &mut arr.open,
+            CstValue::Object(obj) => &mut obj.open,
+        }
+    }
+
+    fn last_mut(&mut self) -> &mut Token<'a> {
+        match self {
+            CstValue::Scalar(token, _) => token,
+            CstValue::Array(arr) => &mut arr.close,
+            CstValue::Object(obj) => &mut obj.close,
+        }
+    }
+
+    /// Replaces `self` with `value`, keeping the leading trivia of the first token and the
+    /// trailing trivia of the last one.
+    fn replace(&mut self, mut value: CstValue<'a>) {
+        value.first_mut().leading = mem::take(&mut self.first_mut().leading);
+        value.last_mut().trailing = mem::take(&mut self.last_mut().trailing);
+        *self = value;
+    }
+}
+
+impl<'a> CstArray<'a> {
+    /// Returns the opening bracket.
+    pub fn open(&self) -> &Token<'a> {
+        &self.open
+    }
+
+    /// Returns the elements.
+    pub fn elements(&self) -> &[CstElement<'a>] {
+        &self.elements
+    }
+
+    /// Returns the closing bracket.
+    pub fn close(&self) -> &Token<'a> {
+        &self.close
+    }
+}
+
+impl<'a> CstElement<'a> {
+    /// Returns the value of the element.
+    pub fn value(&self) -> &CstValue<'a> {
+        &self.value
+    }
+
+    /// Returns the comma following the element, if any.
+    pub fn comma(&self) -> Option<&Token<'a>> {
+        self.comma.as_ref()
+    }
+}
+
+impl<'a> CstObject<'a> {
+    /// Returns the opening brace.
+    pub fn open(&self) -> &Token<'a> {
+        &self.open
+    }
+
+    /// Returns the members in source order, including repeated keys.
+    pub fn members(&self) -> &[CstMember<'a>] {
+        &self.members
+    }
+
+    /// Returns the closing brace.
+    pub fn close(&self) -> &Token<'a> {
+        &self.close
+    }
+}
+
+impl<'a> CstMember<'a> {
+    /// Builds a member with a quoted key, without trivia around it.
+    fn new<K: Into<Cow<'a, str>>>(name: K, value: CstValue<'a>) -> Self {
+        let name = name.into();
+        CstMember {
+            key: Token::new("", format!("\"{}\"", escape(&name)), ""),
+            name,
+            colon: Token::new("", ":", " "),
+            value,
+            comma: None,
+        }
+    }
+
+    /// Returns the key token, as it appears in the source.
+    pub fn key(&self) -> &Token<'a> {
+        &self.key
+    }
+
+    /// Returns the key after unescaping.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the colon between the key and the value.
+    pub fn colon(&self) -> &Token<'a> {
+        &self.colon
+    }
+
+    /// Returns the value of the member.
+    pub fn value(&self) -> &CstValue<'a> {
+        &self.value
+    }
+
+    /// Returns the comma following the member, if any.
+    pub fn comma(&self) -> Option<&Token<'a>> {
+        self.comma.as_ref()
+    }
+}
+
+impl<'a> From<Lexeme<'a>> for Token<'a> {
+    fn from(token: Lexeme<'a>) -> Self {
+        Token::new(token.leading, token.text, token.trailing)
+    }
+}
+
+impl<'a> Syntax<'a> for CstValue<'a> {
+    type Elements = Vec<CstElement<'a>>;
+    /// The members, and their keys when repeated keys are rejected.
+    type Members = (Vec<CstMember<'a>>, HashSet<Cow<'a, str>>);
+
+    fn scalar(token: Lexeme<'a>, json: Json<'a>) -> Self {
+        CstValue::Scalar(token.into(), json)
+    }
+
+    fn push_element(elements: &mut Self::Elements, value: Self, comma: Option<Lexeme<'a>>) {
+        let comma = comma.map(Token::from);
+        elements.push(CstElement { value, comma });
+    }
+
+    fn array(open: Lexeme<'a>, elements: Self::Elements, close: Lexeme<'a>) -> Self {
+        CstValue::Array(CstArray {
+            open: open.into(),
+            elements,
+            close: close.into(),
+        })
+    }
+
+    fn push_member(
+        (members, keys): &mut Self::Members,
+        member: Member<'a, Self>,
+        policy: DuplicateKeys,
+    ) -> bool {
+        if policy == DuplicateKeys::Error && !keys.insert(member.name.clone()) {
+            return false;
+        }
+        members.push(CstMember {
+            key: member.key.into(),
+            name: member.name,
+            colon: member.colon.into(),
+            value: member.value,
+            comma: member.comma.map(Token::from),
+        });
+        true
+    }
+
+    fn object(open: Lexeme<'a>, (members, _): Self::Members, close: Lexeme<'a>) -> Self {
+        CstValue::Object(CstObject {
+            open: open.into(),
+            members,
+            close: close.into(),
+        })
+    }
+}
+
+/// An array element or object member, which can be inserted into or removed from its list.
+trait Item<'a> {
+    fn first_mut(&mut self) -> &mut Token<'a>;
+    fn last_mut(&mut self) -> &mut Token<'a>;
+    fn comma(&mut self) -> &mut Option<Token<'a>>;
+}
+
+impl<'a> Item<'a> for CstElement<'a> {
+    fn first_mut(&mut self) -> &mut Token<'a> {
+        self.value.first_mut()
+    }
+
+    fn last_mut(&mut self) -> &mut Token<'a> {
+        self.value.last_mut()
+    }
+
+    fn comma(&mut self) -> &mut Option<Token<'a>> {
+        &mut self.comma
+    }
+}
+
+impl<'a> Item<'a> for CstMember<'a> {
+    fn first_mut(&mut self) -> &mut Token<'a> {
+        &mut self.key
+    }
+
+    fn last_mut(&mut self) -> &mut Token<'a> {
+        self.value.last_mut()
+    }
+
+    fn comma(&mut self) -> &mut Option<Token<'a>> {
+        &mut self.comma
+    }
+}
+
+/// Returns the line break at the end of `trivia`, if any.
+fn line_break(trivia: &str) -> &str {
+    let start = trivia
+        .strip_suffix("\r\n")
+        .or_else(|| trivia.strip_suffix(['\n', '\r', '\u{2028}', '\u{2029}'].as_ref()))
+        .map_or(trivia.len(), str::len);
+    &trivia[start..]
+}
+
+/// Inserts `item` into `items` before `index`, copying the indentation of a sibling and the
+/// trivia after the commas of the list.
+fn insert_item<'a, T: Item<'a>>(items: &mut Vec<T>, index: usize, mut item: T) {
+    let len = items.len();
+    if len == 0 {
+        items.push(item);
+        return;
+    }
+    item.first_mut().leading = items[index.min(len - 1)].first_mut().leading.clone();
+    // The whitespace after a comma, taken from an existing comma or from the end of the last
+    // line, without comments.
+    let last_break = line_break(&items[len - 1].last_mut().trailing).to_string();
+    let separator = items
+        .iter_mut()
+        .find_map(|item| {
+            item.comma()
+                .as_ref()
+                .map(|comma| comma.trailing.to_string())
+        })
+        .map(|trivia| match line_break(&trivia) {
+            "" if trivia.trim().is_empty() => trivia.clone(),
+            "" => " ".to_string(),
+            line_break => line_break.to_string(),
+        })
+        .or_else(|| Some(last_break.clone()).filter(|sep| !sep.is_empty()))
+        .unwrap_or_else(|| " ".to_string());
+
+    let last = &mut items[len - 1];
+    if index < len || last.comma().is_some() {
+        *item.comma() = Some(Token::new("", ",", separator));
+    } else {
+        // Append after the last item, which gets a comma right after its value. The rest of its
+        // line stays after the comma, and the new item ends its own line the same way.
+        let trailing = mem::take(&mut last.last_mut().trailing);
+        let comma_trailing = if last_break.is_empty() {
+            item.last_mut().trailing = trailing;
+            Cow::from(separator)
+        } else {
+            item.last_mut().trailing = last_break.into();
+            trailing
+        };
+        *last.comma() = Some(Token::new("", ",", comma_trailing));
+    }
+    items.insert(index, item);
+}
+
+/// Removes the item at `index`. When the last item is removed, the comma before it is removed
+/// as well, keeping any comment after the comma.
+fn remove_item<'a, T: Item<'a>>(items: &mut Vec<T>, index: usize) {
+    let mut item = items.remove(index);
+    if item.comma().is_some() || index == 0 {
+        return;
+    }
+    let prev = &mut items[index - 1];
+    let comma = prev.comma().take().unwrap();
+    let kept = &comma.trailing[..comma.trailing.len() - line_break(&comma.trailing).len()];
+    let trailing = &mut prev.last_mut().trailing;
+    *trailing = format!(
+        "{}{}{}",
+        trailing,
+        kept.trim_end(),
+        line_break(&item.last_mut().trailing)
+    )
+    .into();
+}
+
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.write_str(&self.leading)?;
+        f.write_str(&self.text)?;
+        f.write_str(&self.trailing)
+    }
+}
+
+impl Display for CstValue<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            CstValue::Scalar(token, _) => token.fmt(f),
+            CstValue::Array(arr) => {
+                arr.open.fmt(f)?;
+                for element in &arr.elements {
+                    element.value.fmt(f)?;
+                    if let Some(comma) = &element.comma {
+                        comma.fmt(f)?;
+                    }
+                }
+                arr.close.fmt(f)
+            }
+            CstValue::Object(obj) => {
+                obj.open.fmt(f)?;
+                for member in &obj.members {
+                    member.key.fmt(f)?;
+                    member.colon.fmt(f)?;
+                    member.value.fmt(f)?;
+                    if let Some(comma) = &member.comma {
+                        comma.fmt(f)?;
+                    }
+                }
+                obj.close.fmt(f)
+            }
+        }
+    }
+}
+
+impl Display for Cst<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        self.root.fmt(f)?;
+        f.write_str(&self.end)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::JsonErrorKind;
+    use crate::options::{DuplicateKeys, ParserOptions};
+    use crate::parser::{parse_complete, parse_cst};
+    use crate::pointer::{JsonPointer, PointerError};
+    use assert_matches::assert_matches;
+
+    fn edit<F: FnOnce(&mut super::Cst)>(input: &str, f: F) -> String {
+        let options = ParserOptions::new().allow_comments(true);
+        let parser = options.allow_trailing_commas(true).build();
+        let mut cst = parser.parse_cst(input).unwrap();
+        f(&mut cst);
+        cst.to_string()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let jsonc = ParserOptions::new()
+            .allow_comments(true)
+            .allow_trailing_commas(true)
+            .build();
+        let json5 = ParserOptions::new().json5(true).build();
+        for input in &[
+            "null",
+            " \t[ ]\n",
+            "{\r\n  \"a\" : [1 , 2.50, -0e1],\r\n  \"b\": {\"c\\u0041\": \"\\n\"}\r\n}\r\n",
+            "/* head */ [\n  1, // one\n  2 /* two */,\n  // last\n]\n// tail\n",
+        ] {
+            let cst = jsonc.parse_cst(input).unwrap();
+            assert_eq!(cst.to_string(), *input);
+            assert_eq!(cst.to_json(), jsonc.parse_complete(input).unwrap());
+        }
+        let input =
+            "// config\n{unquoted: 'single', hex: 0xFF, inf: -Infinity, trailing: [.5,],}\n";
+        let cst = json5.parse_cst(input).unwrap();
+        assert_eq!(cst.to_string(), input);
+        assert_eq!(cst.to_json(), json5.parse_complete(input).unwrap());
+    }
+
+    #[test]
+    fn test_tokens() {
+        let parser = ParserOptions::new().allow_comments(true).build();
+        let cst = parser.parse_cst("{\"\\u0061\" : 1 // one\n}\n\n").unwrap();
+        let obj = match cst.root() {
+            super::CstValue::Object(obj) => obj,
+            _ => panic!("not an object"),
+        };
+        let member = &obj.members()[0];
+        assert_eq!(member.key().text(), "\"\\u0061\"");
+        assert_eq!(member.name(), "a");
+        assert_eq!(member.key().trailing(), " ");
+        assert_eq!(member.value().first().trailing(), " // one\n");
+        assert_eq!(obj.close().text(), "}");
+        assert_eq!(cst.end(), "\n");
+        let json = cst.get("/a").unwrap().to_json(DuplicateKeys::default());
+        assert_eq!(json, json!(1));
+    }
+
+    #[test]
+    fn test_set() {
+        let input = "{\n  // the answer\n  \"a\": 42, // not 41\n  \"b\": [true]\n}";
+        assert_eq!(
+            edit(input, |cst| cst.set("/a", json!({"x": [1, null]})).unwrap()),
+            "{\n  // the answer\n  \"a\": {\"x\": [1, null]}, // not 41\n  \"b\": [true]\n}"
+        );
+        assert_eq!(
+            edit(input, |cst| cst.set("/b/0", "é\"").unwrap()),
+            "{\n  // the answer\n  \"a\": 42, // not 41\n  \"b\": [\"é\\\"\"]\n}"
+        );
+        assert_eq!(
+            edit(" [1] // end", |cst| cst.set("", 2).unwrap()),
+            " 2 // end"
+        );
+    }
+
+    #[test]
+    fn test_non_finite_floats() {
+        for (json5, expected) in &[
+            (false, r#"[null, 2, {"a": [null]}]"#),
+            (true, r#"[Infinity, 2, {"a": [-Infinity]}]"#),
+        ] {
+            let parser = ParserOptions::new().json5(*json5).build();
+            let mut cst = parser.parse_cst("[1, 2]").unwrap();
+            cst.set("/0", f64::INFINITY).unwrap();
+            cst.insert("/-", json!({"a": [f64::NEG_INFINITY]})).unwrap();
+            let output = cst.to_string();
+            assert_eq!(output, *expected);
+            assert_eq!(parser.parse_complete(&output).unwrap(), cst.to_json());
+        }
+        let parser = ParserOptions::new().json5(true).build();
+        let mut cst = parser.parse_cst("[1]").unwrap();
+        cst.set("/0", f64::NAN).unwrap();
+        assert_eq!(cst.to_string(), "[NaN]");
+    }
+
+    #[test]
+    fn test_insert() {
+        let input = "[\n  1, // one\n  2 // two\n]\n";
+        assert_eq!(
+            edit(input, |cst| cst.insert("/-", 3).unwrap()),
+            "[\n  1, // one\n  2, // two\n  3\n]\n"
+        );
+        assert_eq!(
+            edit(input, |cst| cst.insert("/1", 3).unwrap()),
+            "[\n  1, // one\n  3,\n  2 // two\n]\n"
+        );
+        assert_eq!(
+            edit(input, |cst| cst.insert("/0", 0).unwrap()),
+            "[\n  0,\n  1, // one\n  2 // two\n]\n"
+        );
+        assert_eq!(
+            edit("[1, 2]", |cst| cst.insert("/2", 3).unwrap()),
+            "[1, 2, 3]"
+        );
+        assert_eq!(edit("[]", |cst| cst.insert("/0", 1).unwrap()), "[1]");
+        assert_eq!(
+            edit("{\"a\": 1}", |cst| cst.insert("/b~1c", "d").unwrap()),
+            "{\"a\": 1, \"b/c\": \"d\"}"
+        );
+        assert_eq!(
+            edit("{\"a\": 1}", |cst| cst.insert("/a", 2).unwrap()),
+            "{\"a\": 2}"
+        );
+        assert_eq!(
+            edit("{\n  \"a\": 1,\n}", |cst| cst.insert("/b", 2).unwrap()),
+            "{\n  \"a\": 1,\n  \"b\": 2,\n}"
+        );
+    }
+
+    #[test]
+    fn test_remove() {
+        let input = "[\n  1, // one\n  2, // two\n  3 // three\n]";
+        assert_eq!(
+            edit(input, |cst| cst.remove("/0").unwrap()),
+            "[\n  2, // two\n  3 // three\n]"
+        );
+        assert_eq!(
+            edit(input, |cst| cst.remove("/1").unwrap()),
+            "[\n  1, // one\n  3 // three\n]"
+        );
+        assert_eq!(
+            edit(input, |cst| cst.remove("/2").unwrap()),
+            "[\n  1, // one\n  2 // two\n]"
+        );
+        assert_eq!(
+            edit("{\"a\": [1]}", |cst| cst.remove("/a/0").unwrap()),
+            "{\"a\": []}"
+        );
+        assert_eq!(
+            edit("{\"a\": 1, \"b\": 2}", |cst| cst.remove("/b").unwrap()),
+            "{\"a\": 1}"
+        );
+        assert_eq!(edit("[1] ", |cst| cst.remove("").unwrap()), "null ");
+    }
+
+    #[test]
+    fn test_pointer_errors() {
+        let pointer = |s: &str| s.parse::<JsonPointer>().unwrap();
+        let mut cst = parse_cst(r#"{"a": [1], "b": null}"#).unwrap();
+        assert_eq!(
+            cst.get("/c").unwrap_err(),
+            PointerError::MissingKey(pointer("/c"))
+        );
+        assert_eq!(
+            cst.set("/a/1", 2).unwrap_err(),
+            PointerError::IndexOutOfBounds(pointer("/a/1"))
+        );
+        assert_eq!(
+            cst.insert("/a/2", 2).unwrap_err(),
+            PointerError::IndexOutOfBounds(pointer("/a/2"))
+        );
+        assert_eq!(
+            cst.insert("/b/c", 2).unwrap_err(),
+            PointerError::NotAContainer(pointer("/b/c"))
+        );
+        assert_eq!(
+            cst.remove("/a/x").unwrap_err(),
+            PointerError::InvalidIndex(pointer("/a/x"))
+        );
+        assert_eq!(
+            cst.to_json(),
+            parse_complete(r#"{"a": [1], "b": null}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let input = r#"{"a": 1, "b": {"c": 2, "c": 3}, "a": [4], "d": null, "a": 5}"#;
+        let pointers = [
+            "/a", "/a/0", "/a/1/0", "/a/3", "/a/x", "/b", "/b/c", "/b/c/1", "/d",
+        ];
+        for &policy in &[
+            DuplicateKeys::Error,
+            DuplicateKeys::FirstWins,
+            DuplicateKeys::LastWins,
+            DuplicateKeys::Collect,
+        ] {
+            let parser = ParserOptions::new().duplicate_keys(policy).build();
+            let (cst, json) = match (parser.parse_cst(input), parser.parse_complete(input)) {
+                (Ok(cst), Ok(json)) => (cst, json),
+                (cst, json) => {
+                    assert_eq!(cst.unwrap_err(), json.unwrap_err());
+                    continue;
+                }
+            };
+            assert_eq!(cst.to_json(), json);
+            for pointer in &pointers {
+                match cst.get(pointer) {
+                    Err(PointerError::Collected(path)) => {
+                        assert_eq!(policy, DuplicateKeys::Collect);
+                        assert_matches!(
+                            json.pointer(&path.to_string()).unwrap().as_array(),
+                            Some(_)
+                        );
+                    }
+                    value => assert_eq!(
+                        value.map(|value| value.to_json(policy)),
+                        json.pointer(pointer).cloned()
+                    ),
+                }
+            }
+        }
+
+        let edit = |policy, f: fn(&mut super::Cst) -> Result<(), PointerError>| {
+            let parser = ParserOptions::new().duplicate_keys(policy).build();
+            let mut cst = parser.parse_cst(r#"{"a": 1, "a": 2}"#).unwrap();
+            f(&mut cst).map(|_| cst.to_string())
+        };
+        let set = |cst: &mut super::Cst| cst.set("/a", 0);
+        assert_eq!(
+            edit(DuplicateKeys::FirstWins, set),
+            Ok(r#"{"a": 0, "a": 2}"#.to_string())
+        );
+        assert_eq!(
+            edit(DuplicateKeys::LastWins, set),
+            Ok(r#"{"a": 1, "a": 0}"#.to_string())
+        );
+        assert_eq!(
+            edit(DuplicateKeys::Collect, set),
+            Err(PointerError::Collected("/a".parse().unwrap()))
+        );
+        assert_eq!(
+            edit(DuplicateKeys::Collect, |cst| cst.insert("/a/1", 0)),
+            Err(PointerError::Collected("/a".parse().unwrap()))
+        );
+        assert_eq!(
+            edit(DuplicateKeys::Collect, |cst| cst.set("/a/1", 0)),
+            Ok(r#"{"a": 1, "a": 0}"#.to_string())
+        );
+        assert_eq!(
+            edit(DuplicateKeys::LastWins, |cst| cst.remove("/a")),
+            Ok("{}".to_string())
+        );
+
+        let parser = ParserOptions::new()
+            .duplicate_keys(DuplicateKeys::Collect)
+            .build();
+        let input = r#"{"a": 1, "b": {"a": 0}, "a": 2, "a": [3]}"#;
+        let mut cst = parser.parse_cst(input).unwrap();
+        cst.remove("/a/1").unwrap();
+        assert_eq!(cst.to_string(), r#"{"a": 1, "b": {"a": 0}, "a": [3]}"#);
+        assert_eq!(cst.to_json(), json!({"a": [1, [3]], "b": {"a": 0}}));
+        cst.remove("/a/1/0").unwrap();
+        assert_eq!(
+            cst.remove("/a/2").unwrap_err(),
+            PointerError::IndexOutOfBounds("/a/2".parse().unwrap())
+        );
+        cst.remove("/a").unwrap();
+        assert_eq!(cst.to_string(), r#"{"b": {"a": 0}}"#);
+        cst.remove("/b/a").unwrap();
+        assert_eq!(cst.to_json(), json!({"b": {}}));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let kind = |input| parse_cst(input).unwrap_err().kind();
+        assert_eq!(kind("[1] 2"), JsonErrorKind::TrailingCharacters);
+        assert_eq!(kind("[1,]"), JsonErrorKind::Expected);
+        assert_eq!(kind("[1 // c\n]"), JsonErrorKind::Expected);
+        assert_eq!(kind("{\"a\" 1}"), JsonErrorKind::Expected);
+
+        let options = ParserOptions::new()
+            .max_depth(Some(1))
+            .max_array_len(Some(1));
+        let parser = options.build();
+        assert_eq!(
+            parser.parse_cst("[[]]").unwrap_err(),
+            parser.parse_complete("[[]]").unwrap_err()
+        );
+        assert_eq!(
+            parser.parse_cst("[1, 2]").unwrap_err(),
+            parser.parse_complete("[1, 2]").unwrap_err()
+        );
+    }
+}
@@ -0,0 +1,1165 @@
+use crate::error::{JsonError, JsonErrorKind, JsonParseError, Location};
+use crate::map::Map;
+use crate::number::Number;
+use crate::options::ParserOptions;
+use crate::parser::{
+    parse_false, parse_null, parse_string_raw, parse_true, recognize_number, ParserResult,
+};
+use crate::repr::{Json, JsonValue};
+
+use indexmap::map::Iter;
+use nom::character::complete::multispace0;
+use nom::error::ParseError;
+use nom::Err::{Error, Failure, Incomplete};
+use serde::de::value::{BorrowedStrDeserializer, StringDeserializer};
+use serde::de::{
+    self, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, Unexpected, VariantAccess, Visitor,
+};
+use serde::{forward_to_deserialize_any, Deserialize};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+/// The kind of a [`SerdeError`](struct.SerdeError.html).
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SerdeErrorKind {
+    /// The input is not valid JSON.
+    Syntax(JsonErrorKind),
+    /// The input is valid JSON, but does not match the type being deserialized, or a
+    /// `Deserialize` implementation rejected it. Also used for values that cannot be serialized.
+    Data,
+    /// Writing the output failed.
+    Io(io::ErrorKind),
+}
+
+/// An error returned by the serde support: [`from_str`](fn.from_str.html), the `Deserializer` of
+/// `&Json` and the functions serializing values.
+///
+/// Unlike [`JsonError`](struct.JsonError.html), the error owns its message, so it does not borrow
+/// from the input.
+#[derive(PartialEq, Clone, Debug)]
+pub struct SerdeError {
+    kind: SerdeErrorKind,
+    message: String,
+    location: Option<Location>,
+}
+
+impl SerdeError {
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> SerdeErrorKind {
+        self.kind
+    }
+
+    /// Returns the location of the error in the input, if it was deserialized from text.
+    ///
+    /// For data errors, this is the position right after the value that was rejected.
+    pub fn location(&self) -> Option<Location> {
+        self.location
+    }
+
+    fn syntax(err: JsonError<'_>, source: &str) -> Self {
+        let err = err.locate(source);
+        SerdeError {
+            kind: SerdeErrorKind::Syntax(err.kind()),
+            message: err.to_string(),
+            location: err.location(),
+        }
+    }
+
+    pub(crate) fn io(err: io::Error) -> Self {
+        SerdeError {
+            kind: SerdeErrorKind::Io(err.kind()),
+            message: err.to_string(),
+            location: None,
+        }
+    }
+}
+
+impl de::Error for SerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerdeError {
+            kind: SerdeErrorKind::Data,
+            message: msg.to_string(),
+            location: None,
+        }
+    }
+}
+
+impl Display for SerdeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        match (self.kind, self.location) {
+            (SerdeErrorKind::Data, Some(location)) => {
+                write!(f, " at line {}, column {}", location.line, location.column)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for SerdeError {}
+
+/// Deserializes an instance of `T` from JSON text.
+///
+/// The input is parsed on the fly by the deserializer, without building a
+/// [`Json`](struct.Json.html) first. Strings without escape sequences are borrowed from `input`,
+/// so `T` may contain `&str` fields. Anything but whitespace after the value is an error.
+///
+/// Arrays and objects may be nested up to
+/// [`ParserOptions::DEFAULT_MAX_DEPTH`](struct.ParserOptions.html#associatedconstant.DEFAULT_MAX_DEPTH)
+/// levels deep.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::from_str;
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize, PartialEq, Debug)]
+/// struct User<'a> {
+///     name: &'a str,
+///     age: u8,
+///     emails: Vec<String>,
+/// }
+///
+/// let input = r#"{"name": "Alice", "age": 20, "emails": ["alice@example.com"]}"#;
+/// let user: User = from_str(input).unwrap();
+/// assert_eq!(user.name, "Alice");
+/// assert_eq!(user.emails, ["alice@example.com"]);
+///
+/// let err = from_str::<User>(r#"{"name": "Bob", "age": 256}"#).unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "invalid value: integer `256`, expected u8 at line 1, column 27"
+/// );
+/// ```
+pub fn from_str<'de, T: Deserialize<'de>>(input: &'de str) -> Result<T, SerdeError> {
+    let mut deserializer = Deserializer::from_str(input);
+    let value = T::deserialize(&mut deserializer).map_err(|err| deserializer.locate(err))?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Deserializes an instance of `T` from a [`Json`](struct.Json.html) tree.
+///
+/// This is the same as `T::deserialize(json)`. Strings are always borrowed from the tree.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{from_json, json};
+/// use std::collections::BTreeMap;
+///
+/// let json = json!({"a": [1, 2], "b": []});
+/// let map: BTreeMap<&str, Vec<u8>> = from_json(&json).unwrap();
+/// assert_eq!(map["a"], [1, 2]);
+/// ```
+pub fn from_json<'de, T: Deserialize<'de>>(json: &'de Json<'_>) -> Result<T, SerdeError> {
+    T::deserialize(json)
+}
+
+/// Builds a tree from any serde `Deserializer`, such as a field of type `Json` in a struct that
+/// derives `Deserialize`. Strings are borrowed from the input whenever the deserializer allows it.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{from_str, Json};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Event<'a> {
+///     kind: &'a str,
+///     #[serde(borrow)]
+///     payload: Json<'a>,
+/// }
+///
+/// let event: Event = from_str(r#"{"kind": "click", "payload": {"x": 1, "y": [2]}}"#).unwrap();
+/// assert_eq!(event.payload["y"][0].as_i64(), Some(2));
+/// ```
+impl<'de: 'a, 'a> Deserialize<'de> for Json<'a> {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonVisitor)
+    }
+}
+
+/// Builds a non-null value from any serde `Deserializer`. See the `Deserialize` implementation
+/// of `Json`.
+impl<'de: 'a, 'a> Deserialize<'de> for JsonValue<'a> {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Json::<'a>::deserialize(deserializer)?
+            .0
+            .ok_or_else(|| de::Error::invalid_type(Unexpected::Unit, &"a non-null JSON value"))
+    }
+}
+
+struct JsonVisitor;
+
+impl<'de> Visitor<'de> for JsonVisitor {
+    type Value = Json<'de>;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Json<'de>, E> {
+        Ok(v.into())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Json<'de>, E> {
+        Ok(v.into())
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<Json<'de>, E> {
+        match i64::try_from(v) {
+            Ok(int) => Ok(int.into()),
+            Err(_) if v > 0 => self.visit_u128(v as u128),
+            Err(_) => Ok(Number::new(v.to_string()).unwrap().into()),
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Json<'de>, E> {
+        Ok(v.into())
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Json<'de>, E> {
+        match u64::try_from(v) {
+            Ok(uint) => Ok(uint.into()),
+            Err(_) => Ok(Number::new(v.to_string()).unwrap().into()),
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Json<'de>, E> {
+        Ok(v.into())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Json<'de>, E> {
+        Ok(v.to_string().into())
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Json<'de>, E> {
+        Ok(v.into())
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Json<'de>, E> {
+        Ok(v.into())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Json<'de>, E> {
+        Ok(Json(None))
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Json<'de>, D::Error> {
+        Json::deserialize(deserializer)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Json<'de>, E> {
+        Ok(Json(None))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Json<'de>, A::Error> {
+        let mut arr = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(elem) = seq.next_element()? {
+            arr.push(elem);
+        }
+        Ok(JsonValue::Array(arr).into())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Json<'de>, A::Error> {
+        let mut obj = Map::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(key) = map.next_key::<Json>()? {
+            let key = match key.0 {
+                Some(JsonValue::String(key)) => key,
+                _ => return Err(de::Error::invalid_type(unexpected(&key), &"a string key")),
+            };
+            obj.insert(key, map.next_value::<Json>()?);
+        }
+        Ok(obj.into())
+    }
+}
+
+/// A serde `Deserializer` that parses JSON text.
+///
+/// [`from_str`](fn.from_str.html) covers most uses. The deserializer can be driven directly to
+/// read a value from the start of the input and inspect what follows it.
+pub struct Deserializer<'de> {
+    source: &'de str,
+    input: &'de str,
+    depth: usize,
+}
+
+impl<'de> Deserializer<'de> {
+    /// Creates a deserializer reading from the start of `input`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &'de str) -> Self {
+        Deserializer {
+            source: input,
+            input,
+            depth: 0,
+        }
+    }
+
+    /// Returns the input following the values deserialized so far.
+    pub fn remaining(&self) -> &'de str {
+        self.input
+    }
+
+    /// Checks that only whitespace is left in the input.
+    pub fn end(&mut self) -> Result<(), SerdeError> {
+        if self.peek()?.is_some() {
+            return Err(self.error(JsonErrorKind::TrailingCharacters));
+        }
+        Ok(())
+    }
+
+    /// Runs `parser` on the remaining input and advances past what it consumed.
+    fn parse<O, P>(&mut self, parser: P) -> Result<O, SerdeError>
+    where
+        P: Fn(&'de str) -> ParserResult<'de, O, JsonError<'de>>,
+    {
+        match parser(self.input) {
+            Ok((rest, output)) => {
+                self.input = rest;
+                Ok(output)
+            }
+            Err(Error(err)) | Err(Failure(err)) => Err(SerdeError::syntax(err, self.source)),
+            Err(Incomplete(_)) => Err(self.syntax(JsonError::from_expected(
+                &self.input[self.input.len()..],
+                "more input",
+            ))),
+        }
+    }
+
+    /// Skips whitespace and returns the next character, if any.
+    fn peek(&mut self) -> Result<Option<char>, SerdeError> {
+        self.parse(multispace0)?;
+        Ok(self.input.chars().next())
+    }
+
+    fn syntax(&self, err: JsonError<'de>) -> SerdeError {
+        SerdeError::syntax(err, self.source)
+    }
+
+    fn error(&self, kind: JsonErrorKind) -> SerdeError {
+        self.syntax(JsonError::from_json_error_kind(self.input, kind))
+    }
+
+    fn expected(&self, token: &'static str) -> SerdeError {
+        self.syntax(JsonError::from_expected(self.input, token))
+    }
+
+    /// Fails with a list of expected tokens, like the closing delimiter of an array or object.
+    fn expected_chars(&self, chars: &[char]) -> SerdeError {
+        let err = chars
+            .iter()
+            .map(|&c| JsonError::from_char(self.input, c))
+            .reduce(|lhs, rhs| lhs.or(rhs))
+            .unwrap();
+        self.syntax(err)
+    }
+
+    /// Attaches the current location to a data error.
+    fn locate(&self, mut err: SerdeError) -> SerdeError {
+        if err.location.is_none() {
+            let offset = self.source.len() - self.input.len();
+            err.location = Some(Location::new(self.source, offset));
+        }
+        err
+    }
+
+    fn nested(&mut self) -> Result<(), SerdeError> {
+        if self.depth >= ParserOptions::DEFAULT_MAX_DEPTH {
+            return Err(self.error(JsonErrorKind::DepthLimitExceeded));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn close(&mut self, delimiter: char) -> Result<(), SerdeError> {
+        self.depth -= 1;
+        match self.peek()? {
+            Some(c) if c == delimiter => {
+                self.input = &self.input[1..];
+                Ok(())
+            }
+            _ => Err(self.expected_chars(&[',', delimiter])),
+        }
+    }
+
+    /// Reads the closing bracket of an array. A comma means that the visitor stopped before the
+    /// last element, which is a data error like in the tree deserializer, reported with the
+    /// length of the whole array.
+    fn close_seq(&mut self, mut len: usize) -> Result<(), SerdeError> {
+        if self.peek()? != Some(',') {
+            return self.close(']');
+        }
+        let input = self.input;
+        while self.peek()? == Some(',') {
+            self.input = &self.input[1..];
+            de::IgnoredAny::deserialize(&mut *self)?;
+            len += 1;
+        }
+        self.close(']')?;
+        self.input = input;
+        Err(de::Error::invalid_length(len, &"fewer elements in array"))
+    }
+
+    fn parse_str(&mut self) -> Result<Cow<'de, str>, SerdeError> {
+        self.parse(parse_string_raw)
+    }
+
+    fn deserialize_number<V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value, SerdeError> {
+        let (num_str, is_int) = self.parse(recognize_number)?;
+        if is_int {
+            if let Ok(int) = num_str.parse::<i64>() {
+                return visitor.visit_i64(int);
+            } else if let Ok(uint) = num_str.parse::<u64>() {
+                return visitor.visit_u64(uint);
+            }
+        }
+        visitor.visit_f64(num_str.parse().unwrap())
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.peek()? {
+            Some('n') => {
+                self.parse(parse_null)?;
+                visitor.visit_unit()
+            }
+            Some('t') => {
+                self.parse(parse_true)?;
+                visitor.visit_bool(true)
+            }
+            Some('f') => {
+                self.parse(parse_false)?;
+                visitor.visit_bool(false)
+            }
+            Some('-') | Some('0'..='9') => self.deserialize_number(visitor),
+            Some('"') => match self.parse_str()? {
+                Cow::Borrowed(string) => visitor.visit_borrowed_str(string),
+                Cow::Owned(string) => visitor.visit_string(string),
+            },
+            Some('[') => {
+                self.nested()?;
+                self.input = &self.input[1..];
+                let mut len = 0;
+                let value = visitor.visit_seq(Seq {
+                    de: &mut *self,
+                    len: &mut len,
+                })?;
+                self.close_seq(len)?;
+                Ok(value)
+            }
+            Some('{') => {
+                self.nested()?;
+                self.input = &self.input[1..];
+                let value = visitor.visit_map(Object {
+                    de: &mut *self,
+                    first: true,
+                })?;
+                self.close('}')?;
+                Ok(value)
+            }
+            _ => Err(self.expected("a value")),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        if self.peek()? == Some('n') {
+            self.parse(parse_null)?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.peek()? {
+            Some('"') => visitor.visit_enum(Enum {
+                de: self,
+                unit: true,
+            }),
+            Some('{') => {
+                self.nested()?;
+                self.input = &self.input[1..];
+                let value = visitor.visit_enum(Enum {
+                    de: &mut *self,
+                    unit: false,
+                })?;
+                self.depth -= 1;
+                match self.peek()? {
+                    Some('}') => {
+                        self.input = &self.input[1..];
+                        Ok(value)
+                    }
+                    _ => Err(self.expected_chars(&['}'])),
+                }
+            }
+            _ => Err(self.expected("a string or an object")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+/// Reads the elements of an array, after the opening bracket.
+struct Seq<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    len: &'a mut usize,
+}
+
+impl<'de> SeqAccess<'de> for Seq<'_, 'de> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerdeError> {
+        match self.de.peek()? {
+            Some(']') => return Ok(None),
+            Some(',') if *self.len > 0 => self.de.input = &self.de.input[1..],
+            _ if *self.len > 0 => return Err(self.de.expected_chars(&[',', ']'])),
+            _ => {}
+        }
+        *self.len += 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+/// Reads the members of an object, after the opening brace.
+struct Object<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    first: bool,
+}
+
+impl<'de> MapAccess<'de> for Object<'_, 'de> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        match self.de.peek()? {
+            Some('}') => return Ok(None),
+            Some(',') if !self.first => self.de.input = &self.de.input[1..],
+            _ if !self.first => return Err(self.de.expected_chars(&[',', '}'])),
+            _ => self.first = false,
+        }
+        match self.de.peek()? {
+            Some('"') => seed.deserialize(Key(self.de.parse_str()?)).map(Some),
+            _ => Err(self.de.expected("a string")),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.de.peek()? {
+            Some(':') => self.de.input = &self.de.input[1..],
+            _ => return Err(self.de.expected_chars(&[':'])),
+        }
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+/// Deserializes an object key. Keys are strings, which integer, float and boolean types parse, so
+/// that maps such as `BTreeMap<u32, T>` read the keys they are serialized to.
+struct Key<'de>(Cow<'de, str>);
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+                match self.0.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(de::Error::invalid_type(Unexpected::Str(&self.0), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Key<'de> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.0 {
+            Cow::Borrowed(key) => visitor.visit_borrowed_str(key),
+            Cow::Owned(key) => visitor.visit_string(key),
+        }
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.0 {
+            Cow::Borrowed(key) => {
+                BorrowedStrDeserializer::new(key).deserialize_enum(name, variants, visitor)
+            }
+            Cow::Owned(key) => {
+                StringDeserializer::new(key).deserialize_enum(name, variants, visitor)
+            }
+        }
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Reads an enum, either a unit variant as a string or any variant as an object with a single
+/// member.
+struct Enum<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    unit: bool,
+}
+
+impl<'a, 'de> EnumAccess<'de> for Enum<'a, 'de> {
+    type Error = SerdeError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), SerdeError> {
+        if !self.unit && self.de.peek()? != Some('"') {
+            return Err(self.de.expected("a string"));
+        }
+        let variant = seed.deserialize(&mut *self.de)?;
+        if !self.unit {
+            match self.de.peek()? {
+                Some(':') => self.de.input = &self.de.input[1..],
+                _ => return Err(self.de.expected_chars(&[':'])),
+            }
+        }
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Enum<'_, 'de> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        if self.unit {
+            Ok(())
+        } else {
+            de::Deserialize::deserialize(self.de)
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        if self.unit {
+            return Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"newtype variant",
+            ));
+        }
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        if self.unit {
+            return Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"tuple variant",
+            ));
+        }
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        if self.unit {
+            return Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"struct variant",
+            ));
+        }
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
+/// Deserializes from a parsed tree. Strings are borrowed from the tree for the lifetime of the
+/// reference.
+impl<'de, 'a> de::Deserializer<'de> for &'de Json<'a> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        let value = match self.as_value() {
+            Some(value) => value,
+            None => return visitor.visit_unit(),
+        };
+        match value {
+            JsonValue::Int(int) => visitor.visit_i64(*int),
+            JsonValue::UInt(uint) => visitor.visit_u64(*uint),
+            JsonValue::Float(float) => visitor.visit_f64(*float),
+            JsonValue::Number(number) => {
+                if let Some(int) = number.as_i64() {
+                    visitor.visit_i64(int)
+                } else if let Some(uint) = number.as_u64() {
+                    visitor.visit_u64(uint)
+                } else if let Some(float) = number.as_f64() {
+                    visitor.visit_f64(float)
+                } else {
+                    Err(de::Error::invalid_value(
+                        Unexpected::Other(number.as_str()),
+                        &"a number in range",
+                    ))
+                }
+            }
+            JsonValue::String(string) => visitor.visit_borrowed_str(string),
+            JsonValue::Bool(boolean) => visitor.visit_bool(*boolean),
+            JsonValue::Array(arr) => {
+                let mut seq = TreeSeq(arr.iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                if seq.0.len() == 0 {
+                    Ok(value)
+                } else {
+                    Err(de::Error::invalid_length(
+                        arr.len(),
+                        &"fewer elements in array",
+                    ))
+                }
+            }
+            JsonValue::Object(obj) => visitor.visit_map(TreeObject {
+                members: obj.iter(),
+                value: None,
+            }),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        if self.is_null() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        if let Some(variant) = self.as_str() {
+            return visitor.visit_enum(TreeEnum {
+                variant,
+                value: None,
+            });
+        }
+        match self.as_object() {
+            Some(obj) if obj.len() == 1 => {
+                let (variant, value) = obj.iter().next().unwrap();
+                visitor.visit_enum(TreeEnum {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            _ => Err(de::Error::invalid_type(
+                unexpected(self),
+                &"a string or an object with a single member",
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+fn unexpected<'de>(json: &'de Json<'_>) -> Unexpected<'de> {
+    match json.as_value() {
+        None => Unexpected::Unit,
+        Some(JsonValue::Int(int)) => Unexpected::Signed(*int),
+        Some(JsonValue::UInt(uint)) => Unexpected::Unsigned(*uint),
+        Some(JsonValue::Float(float)) => Unexpected::Float(*float),
+        Some(JsonValue::Number(number)) => Unexpected::Other(number.as_str()),
+        Some(JsonValue::String(string)) => Unexpected::Str(string),
+        Some(JsonValue::Bool(boolean)) => Unexpected::Bool(*boolean),
+        Some(JsonValue::Array(_)) => Unexpected::Seq,
+        Some(JsonValue::Object(_)) => Unexpected::Map,
+    }
+}
+
+struct TreeSeq<'de, 'a>(std::slice::Iter<'de, Json<'a>>);
+
+impl<'de> SeqAccess<'de> for &mut TreeSeq<'de, '_> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerdeError> {
+        self.0.next().map(|elem| seed.deserialize(elem)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct TreeObject<'de, 'a> {
+    members: Iter<'de, Cow<'a, str>, Json<'a>>,
+    value: Option<&'de Json<'a>>,
+}
+
+impl<'de> MapAccess<'de> for TreeObject<'de, '_> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        match self.members.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(Key(Cow::Borrowed(key))).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.members.len())
+    }
+}
+
+struct TreeEnum<'de, 'a> {
+    variant: &'de str,
+    value: Option<&'de Json<'a>>,
+}
+
+impl<'de, 'a> EnumAccess<'de> for TreeEnum<'de, 'a> {
+    type Error = SerdeError;
+    type Variant = TreeVariant<'de, 'a>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), SerdeError> {
+        let variant = seed.deserialize(BorrowedStrDeserializer::new(self.variant))?;
+        Ok((variant, TreeVariant(self.value)))
+    }
+}
+
+/// The value of an enum variant in a tree, or `None` for a unit variant written as a string.
+struct TreeVariant<'de, 'a>(Option<&'de Json<'a>>);
+
+impl<'de> VariantAccess<'de> for TreeVariant<'de, '_> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        match self.0 {
+            Some(value) => Deserialize::deserialize(value),
+            None => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        match self.0 {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.0 {
+            Some(value) => de::Deserializer::deserialize_seq(value, visitor),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.0 {
+            Some(value) => de::Deserializer::deserialize_map(value, visitor),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_complete;
+    use serde_derive::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Point(i32, i32),
+        Rect { w: u32, h: u32 },
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Record<'a> {
+        id: u64,
+        name: &'a str,
+        #[serde(borrow)]
+        note: Cow<'a, str>,
+        tags: Vec<String>,
+        parent: Option<Box<Record<'a>>>,
+        shapes: Vec<Shape>,
+        extra: BTreeMap<String, bool>,
+    }
+
+    const RECORD: &str = r#"{
+        "id": 18446744073709551615,
+        "name": "root",
+        "note": "line\nbreak",
+        "tags": [],
+        "parent": {
+            "id": 1, "name": "p", "note": "plain", "tags": ["a"], "parent": null,
+            "shapes": [], "extra": {}
+        },
+        "shapes": ["Empty", {"Circle": 1.5}, {"Point": [-1, 2]}, {"Rect": {"w": 3, "h": 4}}],
+        "extra": {"x": true}
+    }"#;
+
+    fn check_record(record: &Record) {
+        assert_eq!(record.id, u64::MAX);
+        assert_eq!(record.note, "line\nbreak");
+        let parent = record.parent.as_ref().unwrap();
+        assert_eq!(
+            (parent.name, parent.tags.as_slice()),
+            ("p", &["a".into()][..])
+        );
+        assert_eq!(
+            record.shapes,
+            [
+                Shape::Empty,
+                Shape::Circle(1.5),
+                Shape::Point(-1, 2),
+                Shape::Rect { w: 3, h: 4 }
+            ]
+        );
+        assert_eq!(record.extra.get("x"), Some(&true));
+    }
+
+    #[test]
+    fn test_from_str() {
+        let record: Record = from_str(RECORD).unwrap();
+        check_record(&record);
+        assert!(matches!(record.note, Cow::Owned(_)));
+        assert!(matches!(
+            record.parent.unwrap().note,
+            Cow::Borrowed("plain")
+        ));
+
+        assert_eq!(
+            from_str::<(u8, f32, ())>(" [1, 2, null] "),
+            Ok((1, 2.0, ()))
+        );
+        assert_eq!(from_str::<Option<Shape>>("null"), Ok(None));
+        assert_eq!(from_str::<char>(r#""é""#), Ok('é'));
+
+        let ints = r#"{"2": -1, "10": 1, "-3": 0}"#;
+        let expected: BTreeMap<i32, i8> = vec![(2, -1), (10, 1), (-3, 0)].into_iter().collect();
+        assert_eq!(from_str(ints), Ok(expected.clone()));
+        assert_eq!(from_json(&parse_complete(ints).unwrap()), Ok(expected));
+        let floats = from_str::<BTreeMap<String, BTreeMap<u64, f64>>>(r#"{"a": {"1": 1.5}}"#);
+        assert_eq!(floats.unwrap()["a"][&1], 1.5);
+    }
+
+    #[test]
+    fn test_from_json() {
+        let json = parse_complete(RECORD).unwrap();
+        let record: Record = from_json(&json).unwrap();
+        check_record(&record);
+        assert!(matches!(record.note, Cow::Borrowed(_)));
+
+        let json = crate::Parser::with_options(ParserOptions::new().lossless_numbers(true))
+            .parse_complete("[1e2, -3, 18446744073709551615]")
+            .unwrap();
+        assert_eq!(from_json(&json), Ok((100.0, -3i8, u64::MAX)));
+        assert_eq!(
+            from_json::<Vec<u8>>(&json).unwrap_err().kind(),
+            SerdeErrorKind::Data
+        );
+    }
+
+    #[test]
+    fn test_data_errors() {
+        let err = from_str::<Vec<u8>>("[1,\n 300]").unwrap_err();
+        assert_eq!(err.kind(), SerdeErrorKind::Data);
+        assert_eq!(
+            err.to_string(),
+            "invalid value: integer `300`, expected u8 at line 2, column 5"
+        );
+
+        let err = from_str::<Shape>(r#"{"Square": 1}"#).unwrap_err();
+        assert!(err.to_string().starts_with("unknown variant `Square`"));
+
+        let json = parse_complete(r#"{"Circle": 1, "Empty": null}"#).unwrap();
+        let err = from_json::<Shape>(&json).unwrap_err();
+        assert_eq!(err.location(), None);
+        assert_eq!(
+            err.to_string(),
+            "invalid type: map, expected a string or an object with a single member"
+        );
+        let err = from_str::<BTreeMap<u8, u8>>(r#"{"1": 1, "x": 2}"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid type: string \"x\", expected u8 at line 1, column 13"
+        );
+
+        let input = "[1, 2, 3, [4]]";
+        let err = from_str::<(u8, u8)>(input).unwrap_err();
+        assert_eq!(err.kind(), SerdeErrorKind::Data);
+        assert_eq!(
+            err.to_string(),
+            "invalid length 4, expected fewer elements in array at line 1, column 6"
+        );
+        let err = from_json::<(u8, u8)>(&parse_complete(input).unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid length 4, expected fewer elements in array"
+        );
+        assert_eq!(
+            from_str::<(u8, u8)>("[1, 2, 3").unwrap_err().kind(),
+            SerdeErrorKind::Syntax(JsonErrorKind::Expected)
+        );
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let syntax = |input| match from_str::<Vec<Option<u8>>>(input).unwrap_err().kind() {
+            SerdeErrorKind::Syntax(kind) => kind,
+            kind => panic!("unexpected kind: {:?}", kind),
+        };
+        assert_eq!(syntax("[1 2]"), JsonErrorKind::Expected);
+        assert_eq!(syntax("[1,]"), JsonErrorKind::Expected);
+        assert_eq!(syntax("[01]"), JsonErrorKind::LeadingZero);
+        assert_eq!(
+            syntax("[nul]"),
+            JsonErrorKind::Nom(nom::error::ErrorKind::Tag)
+        );
+        assert_eq!(syntax("[1] 2"), JsonErrorKind::TrailingCharacters);
+
+        let err = from_str::<BTreeMap<String, u8>>(r#"{"a": 1 "b": 2}"#).unwrap_err();
+        assert_eq!(err.location().map(|location| location.column), Some(9));
+        assert!(err
+            .to_string()
+            .starts_with("expected `,` or `}` at line 1, column 9"));
+
+        let deep = "[".repeat(ParserOptions::DEFAULT_MAX_DEPTH + 1);
+        let err = from_str::<de::IgnoredAny>(&deep).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            SerdeErrorKind::Syntax(JsonErrorKind::DepthLimitExceeded)
+        );
+    }
+
+    #[test]
+    fn test_deserializer() {
+        let mut deserializer = Deserializer::from_str("[1] {}");
+        let first: Vec<u8> = Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(first, [1]);
+        assert_eq!(deserializer.remaining(), " {}");
+        assert!(deserializer.end().is_err());
+    }
+}
@@ -0,0 +1,596 @@
+use crate::patch::{Patch, PatchOperation};
+use crate::pointer::JsonPointer;
+use crate::repr::{Json, JsonValue};
+use crate::utils::{escape, PadAdapter};
+
+use itertools::Itertools;
+use std::fmt::{Display, Error, Formatter, Write};
+
+/// Options controlling how differences between two values are computed and displayed.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{parse_complete, DiffOptions};
+///
+/// let old = parse_complete("[1, 2, 3, 4]").unwrap();
+/// let new = parse_complete("[0, 1, 2, 3, 4]").unwrap();
+///
+/// let options = DiffOptions::new().lcs_arrays(true);
+/// let patch = old.diff_with_options(&new, &options);
+/// assert_eq!(patch.to_json().to_string(), r#"[{"op": "add", "path": "/0", "value": 0}]"#);
+/// assert_eq!(old.diff(&new).operations().len(), 5);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DiffOptions {
+    pub(crate) lcs_arrays: bool,
+    pub(crate) color: bool,
+}
+
+impl DiffOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Aligns array elements by their longest common subsequence, so that inserting or removing
+    /// an element only affects that element. Otherwise elements are compared index by index.
+    /// Disabled by default.
+    ///
+    /// The alignment takes time proportional to the product of the array lengths, not counting
+    /// their common prefix and suffix, and memory proportional to their sum.
+    pub fn lcs_arrays(mut self, enabled: bool) -> Self {
+        self.lcs_arrays = enabled;
+        self
+    }
+
+    /// Colors removed lines red and added lines green with ANSI escape codes when displaying a
+    /// [`DiffDisplay`](struct.DiffDisplay.html). Disabled by default.
+    pub fn color(mut self, enabled: bool) -> Self {
+        self.color = enabled;
+        self
+    }
+}
+
+/// A human-readable rendering of the differences between two values, created by
+/// [`Json::diff_display`](struct.Json.html#method.diff_display).
+///
+/// Objects and arrays that contain differences are expanded, with one member or element per
+/// line. Removed lines start with `-` and added lines with `+`. Everything else is printed on a
+/// single line.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{parse_complete, DiffOptions};
+///
+/// let old = parse_complete(r#"{"name": "Frank", "age": 18, "tags": ["a", "b"]}"#).unwrap();
+/// let new = parse_complete(r#"{"name": "Frank", "age": 19, "tags": ["b"]}"#).unwrap();
+/// let options = DiffOptions::new().lcs_arrays(true);
+/// assert_eq!(
+///     old.diff_display(&new, &options).to_string(),
+///     r#"  {
+///       "name": "Frank",
+///     - "age": 18
+///     + "age": 19,
+///       "tags": [
+///         - "a",
+///           "b"
+///       ]
+///   }"#
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct DiffDisplay<'j, 'a> {
+    source: &'j Json<'a>,
+    target: &'j Json<'a>,
+    options: DiffOptions,
+}
+
+impl<'a> Json<'a> {
+    /// Returns a JSON Patch that turns `self` into `target`, comparing arrays index by index.
+    pub fn diff(&self, target: &Json<'a>) -> Patch<'a> {
+        self.diff_with_options(target, &DiffOptions::default())
+    }
+
+    /// Returns a JSON Patch that turns `self` into `target`.
+    ///
+    /// The patch only uses `add`, `remove` and `replace` operations. Members and elements that
+    /// are present on both sides are diffed recursively, and values of different types are
+    /// replaced.
+    pub fn diff_with_options(&self, target: &Json<'a>, options: &DiffOptions) -> Patch<'a> {
+        let mut operations = Vec::new();
+        diff(
+            self,
+            target,
+            &mut JsonPointer::new(),
+            options,
+            &mut operations,
+        );
+        Patch::new(operations)
+    }
+
+    /// Returns a human-readable rendering of the differences between `self` and `target`.
+    pub fn diff_display<'j>(
+        &'j self,
+        target: &'j Json<'a>,
+        options: &DiffOptions,
+    ) -> DiffDisplay<'j, 'a> {
+        DiffDisplay {
+            source: self,
+            target,
+            options: options.clone(),
+        }
+    }
+}
+
+fn diff<'a>(
+    source: &Json<'a>,
+    target: &Json<'a>,
+    path: &mut JsonPointer,
+    options: &DiffOptions,
+    operations: &mut Vec<PatchOperation<'a>>,
+) {
+    match (source.as_value(), target.as_value()) {
+        (Some(JsonValue::Object(source)), Some(JsonValue::Object(target))) => {
+            for (key, value) in source {
+                path.push(key.to_string());
+                match target.get(key) {
+                    Some(new) => diff(value, new, path, options, operations),
+                    None => operations.push(PatchOperation::Remove { path: path.clone() }),
+                }
+                path.pop();
+            }
+            for (key, value) in target {
+                if !source.contains_key(key) {
+                    operations.push(PatchOperation::Add {
+                        path: path.clone().child(key.to_string()),
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+        (Some(JsonValue::Array(source)), Some(JsonValue::Array(target))) => {
+            let mut index = 0;
+            for step in align(source, target, options.lcs_arrays) {
+                path.push(index.to_string());
+                match step {
+                    Step::Pair(old, new) => {
+                        diff(old, new, path, options, operations);
+                        index += 1;
+                    }
+                    Step::Remove(_) => {
+                        operations.push(PatchOperation::Remove { path: path.clone() })
+                    }
+                    Step::Insert(new) => {
+                        operations.push(PatchOperation::Add {
+                            path: path.clone(),
+                            value: new.clone(),
+                        });
+                        index += 1;
+                    }
+                }
+                path.pop();
+            }
+        }
+        _ if source == target => {}
+        _ => operations.push(PatchOperation::Replace {
+            path: path.clone(),
+            value: target.clone(),
+        }),
+    }
+}
+
+/// A step in the alignment of two arrays.
+#[derive(Clone, Copy)]
+enum Step<'j, 'a> {
+    /// An element of the source that corresponds to an element of the target.
+    Pair(&'j Json<'a>, &'j Json<'a>),
+    /// An element of the source that is not in the target.
+    Remove(&'j Json<'a>),
+    /// An element of the target that is not in the source.
+    Insert(&'j Json<'a>),
+}
+
+fn align<'j, 'a>(source: &'j [Json<'a>], target: &'j [Json<'a>], lcs: bool) -> Vec<Step<'j, 'a>> {
+    let pairs = |source: &'j [Json<'a>], target: &'j [Json<'a>]| {
+        source
+            .iter()
+            .zip(target)
+            .map(|(old, new)| Step::Pair(old, new))
+    };
+    if !lcs {
+        let common = source.len().min(target.len());
+        return pairs(source, target)
+            .chain(source[common..].iter().map(Step::Remove))
+            .chain(target[common..].iter().map(Step::Insert))
+            .collect();
+    }
+
+    let prefix = pairs(source, target)
+        .take_while(|step| matches!(step, Step::Pair(old, new) if old == new))
+        .count();
+    let suffix = source[prefix..]
+        .iter()
+        .rev()
+        .zip(target[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let (old, new) = (
+        &source[prefix..source.len() - suffix],
+        &target[prefix..target.len() - suffix],
+    );
+
+    let mut matches = Vec::new();
+    lcs_matches(old, new, (0, 0), &mut matches);
+
+    let mut steps = pairs(&source[..prefix], target).collect::<Vec<_>>();
+    let (mut removed, mut inserted) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in matches {
+        removed.extend(&old[i..next_i]);
+        inserted.extend(&new[j..next_j]);
+        pair_up(&mut steps, &mut removed, &mut inserted);
+        steps.push(Step::Pair(&old[next_i], &new[next_j]));
+        i = next_i + 1;
+        j = next_j + 1;
+    }
+    removed.extend(&old[i..]);
+    inserted.extend(&new[j..]);
+    pair_up(&mut steps, &mut removed, &mut inserted);
+    steps.extend(pairs(
+        &source[source.len() - suffix..],
+        &target[target.len() - suffix..],
+    ));
+    steps
+}
+
+/// Appends the indices of the matching elements of a longest common subsequence of `old` and
+/// `new` to `matches`, shifted by `offset`. This is Hirschberg's algorithm, which splits `old` in
+/// half and finds where the subsequence crosses the split from the lengths of both halves, so it
+/// only keeps one row of lengths at a time.
+fn lcs_matches(
+    old: &[Json<'_>],
+    new: &[Json<'_>],
+    offset: (usize, usize),
+    matches: &mut Vec<(usize, usize)>,
+) {
+    if old.is_empty() || new.is_empty() {
+        return;
+    }
+    if old.len() == 1 {
+        if let Some(j) = new.iter().position(|new| *new == old[0]) {
+            matches.push((offset.0, offset.1 + j));
+        }
+        return;
+    }
+    let mid = old.len() / 2;
+    let forward = lcs_lengths(old[..mid].iter(), new.iter());
+    let backward = lcs_lengths(old[mid..].iter().rev(), new.iter().rev());
+    let split = (0..=new.len())
+        .max_by_key(|&j| forward[j] + backward[new.len() - j])
+        .unwrap_or(0);
+    lcs_matches(&old[..mid], &new[..split], offset, matches);
+    let offset = (offset.0 + mid, offset.1 + split);
+    lcs_matches(&old[mid..], &new[split..], offset, matches);
+}
+
+/// Returns the lengths of the longest common subsequences of `old` and each prefix of `new`.
+fn lcs_lengths<'j, 'a: 'j>(
+    old: impl Iterator<Item = &'j Json<'a>>,
+    new: impl Iterator<Item = &'j Json<'a>>,
+) -> Vec<usize> {
+    let new = new.collect::<Vec<_>>();
+    let mut lengths = vec![0; new.len() + 1];
+    for old in old {
+        // the length for the previous element of `old` and the previous prefix of `new`
+        let mut diagonal = 0;
+        for (j, new) in new.iter().enumerate() {
+            let above = lengths[j + 1];
+            lengths[j + 1] = if old == *new {
+                diagonal + 1
+            } else {
+                above.max(lengths[j])
+            };
+            diagonal = above;
+        }
+    }
+    lengths
+}
+
+/// Turns a run of removed and inserted elements into steps, pairing them up first so that
+/// changed elements are diffed instead of replaced.
+fn pair_up<'j, 'a>(
+    steps: &mut Vec<Step<'j, 'a>>,
+    removed: &mut Vec<&'j Json<'a>>,
+    inserted: &mut Vec<&'j Json<'a>>,
+) {
+    let common = removed.len().min(inserted.len());
+    steps.extend(
+        removed
+            .iter()
+            .zip(inserted.iter())
+            .map(|(old, new)| Step::Pair(old, new)),
+    );
+    steps.extend(removed.drain(..).skip(common).map(Step::Remove));
+    steps.extend(inserted.drain(..).skip(common).map(Step::Insert));
+}
+
+/// A member or element of a diffed object or array, printed on one or more lines.
+struct Entry<'j, 'a> {
+    key: Option<&'j str>,
+    step: Step<'j, 'a>,
+    options: &'j DiffOptions,
+}
+
+/// The differences between two objects or two arrays.
+struct Node<'j, 'a> {
+    open: &'static str,
+    close: &'static str,
+    entries: Vec<Entry<'j, 'a>>,
+}
+
+impl<'j, 'a> Node<'j, 'a> {
+    fn new(source: &'j Json<'a>, target: &'j Json<'a>, options: &'j DiffOptions) -> Option<Self> {
+        let entry = |key, step| Entry { key, step, options };
+        match (source.as_value()?, target.as_value()?) {
+            (JsonValue::Object(source), JsonValue::Object(target)) => {
+                let old = source.iter().map(|(key, old)| match target.get(key) {
+                    Some(new) => entry(Some(key), Step::Pair(old, new)),
+                    None => entry(Some(key), Step::Remove(old)),
+                });
+                let added = target
+                    .iter()
+                    .filter(|(key, _)| !source.contains_key(key))
+                    .map(|(key, new)| entry(Some(key), Step::Insert(new)));
+                Some(Node {
+                    open: "{",
+                    close: "}",
+                    entries: old.chain(added).collect(),
+                })
+            }
+            (JsonValue::Array(source), JsonValue::Array(target)) => Some(Node {
+                open: "[",
+                close: "]",
+                entries: align(source, target, options.lcs_arrays)
+                    .into_iter()
+                    .map(|step| entry(None, step))
+                    .collect(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Node<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let mut f: PadAdapter = f.into();
+        f.write_str(self.open)?;
+        f.write_str("\n")?;
+        f.write_fmt(format_args!("{}", self.entries.iter().format(",\n")))?;
+        let f = f.into_inner();
+        f.write_str("\n  ")?;
+        f.write_str(self.close)
+    }
+}
+
+impl Entry<'_, '_> {
+    fn write_line(
+        &self,
+        f: &mut Formatter<'_>,
+        marker: char,
+        value: &Json<'_>,
+    ) -> Result<(), Error> {
+        if self.options.color {
+            f.write_str(if marker == '-' {
+                "\x1b[31m"
+            } else {
+                "\x1b[32m"
+            })?;
+        }
+        write!(f, "{} ", marker)?;
+        self.write_key(f)?;
+        write!(f, "{}", value)?;
+        if self.options.color {
+            f.write_str("\x1b[0m")?;
+        }
+        Ok(())
+    }
+
+    fn write_key(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self.key {
+            Some(key) => write!(f, "\"{}\": ", escape(key)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for Entry<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self.step {
+            Step::Pair(old, new) if old == new => {
+                f.write_str("  ")?;
+                self.write_key(f)?;
+                write!(f, "{}", old)
+            }
+            Step::Pair(old, new) => match Node::new(old, new, self.options) {
+                Some(node) => {
+                    f.write_str("  ")?;
+                    self.write_key(f)?;
+                    write!(f, "{}", node)
+                }
+                None => {
+                    self.write_line(f, '-', old)?;
+                    f.write_str("\n")?;
+                    self.write_line(f, '+', new)
+                }
+            },
+            Step::Remove(old) => self.write_line(f, '-', old),
+            Step::Insert(new) => self.write_line(f, '+', new),
+        }
+    }
+}
+
+impl Display for DiffDisplay<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        Entry {
+            key: None,
+            step: Step::Pair(self.source, self.target),
+            options: &self.options,
+        }
+        .fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_complete;
+    use prop::collection::{btree_map, vec};
+    use proptest::prelude::*;
+
+    fn diff(source: &str, target: &str, lcs: bool) -> String {
+        let source = parse_complete(source).unwrap();
+        let target = parse_complete(target).unwrap();
+        let options = DiffOptions::new().lcs_arrays(lcs);
+        source
+            .diff_with_options(&target, &options)
+            .to_json()
+            .to_string()
+    }
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(
+            diff(r#"{"a": [1, {"b": 2}]}"#, r#"{"a": [1, {"b": 2}]}"#, false),
+            "[]"
+        );
+        assert_eq!(
+            diff("1", "[1]", false),
+            r#"[{"op": "replace", "path": "", "value": [1]}]"#
+        );
+        assert_eq!(
+            diff(
+                r#"{"a": 1, "b": {"c": 1, "d": 2}}"#,
+                r#"{"b": {"c": 3, "d~/": 2}, "e": null}"#,
+                false
+            ),
+            r#"[{"op": "remove", "path": "/a"}, {"op": "replace", "path": "/b/c", "value": 3}, {"op": "remove", "path": "/b/d"}, {"op": "add", "path": "/b/d~0~1", "value": 2}, {"op": "add", "path": "/e", "value": null}]"#
+        );
+        assert_eq!(
+            diff("[1, 2, 3]", "[1, 3]", false),
+            r#"[{"op": "replace", "path": "/1", "value": 3}, {"op": "remove", "path": "/2"}]"#
+        );
+        assert_eq!(
+            diff("[1, 2]", "[1, 2, 3, 4]", false),
+            r#"[{"op": "add", "path": "/2", "value": 3}, {"op": "add", "path": "/3", "value": 4}]"#
+        );
+    }
+
+    #[test]
+    fn test_diff_lcs() {
+        assert_eq!(
+            diff("[1, 2, 3]", "[1, 3]", true),
+            r#"[{"op": "remove", "path": "/1"}]"#
+        );
+        assert_eq!(
+            diff("[1, 2, 3, 4, 5]", "[0, 1, 3, 4, 6, 5]", true),
+            r#"[{"op": "add", "path": "/0", "value": 0}, {"op": "remove", "path": "/2"}, {"op": "add", "path": "/4", "value": 6}]"#
+        );
+        assert_eq!(
+            diff(
+                r#"[1, {"id": 2, "x": 0}, 3]"#,
+                r#"[1, {"id": 2, "x": 1}, 3]"#,
+                true
+            ),
+            r#"[{"op": "replace", "path": "/1/x", "value": 1}]"#
+        );
+        assert_eq!(
+            diff("[1, 2, 3]", "[4, 5]", true),
+            r#"[{"op": "replace", "path": "/0", "value": 4}, {"op": "replace", "path": "/1", "value": 5}, {"op": "remove", "path": "/2"}]"#
+        );
+    }
+
+    #[test]
+    fn test_diff_display() {
+        let source = parse_complete(r#"{"a": [1, 2, {"b": true}], "c": "x", "d": {}}"#).unwrap();
+        let target = parse_complete(r#"{"a": [1, {"b": false}], "d": {}, "e": [1]}"#).unwrap();
+        let options = DiffOptions::new();
+        assert_eq!(
+            source.diff_display(&target, &options).to_string(),
+            "  {\n      \"a\": [\n          1,\n        - 2\n        + {\"b\": false},\n        - {\"b\": true}\n      ],\n    - \"c\": \"x\",\n      \"d\": {},\n    + \"e\": [1]\n  }"
+        );
+        let options = DiffOptions::new().lcs_arrays(true).color(true);
+        assert_eq!(
+            source.diff_display(&target, &options).to_string(),
+            "  {\n      \"a\": [\n          1,\n        \u{1b}[31m- 2\u{1b}[0m\n        \u{1b}[32m+ {\"b\": false}\u{1b}[0m,\n        \u{1b}[31m- {\"b\": true}\u{1b}[0m\n      ],\n    \u{1b}[31m- \"c\": \"x\"\u{1b}[0m,\n      \"d\": {},\n    \u{1b}[32m+ \"e\": [1]\u{1b}[0m\n  }"
+        );
+        let target = parse_complete("null").unwrap();
+        assert_eq!(
+            source.diff_display(&source, &options).to_string(),
+            format!("  {}", source)
+        );
+        assert_eq!(
+            Json::from(1)
+                .diff_display(&target, &DiffOptions::new())
+                .to_string(),
+            "- 1\n+ null"
+        );
+    }
+
+    fn arb_json() -> impl Strategy<Value = Json<'static>> {
+        let leaf = prop_oneof![
+            Just(None.into()),
+            any::<bool>().prop_map(Into::into),
+            (0..4i64).prop_map(Into::into),
+            "[ab]".prop_map(|s| s.into()),
+        ];
+        leaf.prop_recursive(4, 64, 6, |inner| {
+            prop_oneof![
+                vec(inner.clone(), 0..6).prop_map(Into::into),
+                btree_map("[abc]", inner.clone(), 0..4).prop_map(Into::into)
+            ]
+        })
+    }
+
+    #[test]
+    fn test_diff_lcs_long() {
+        let source = (0..3000).collect::<Vec<i64>>();
+        let mut target = source.clone();
+        target.retain(|i| i % 500 != 0);
+        target.insert(1000, -1);
+        target.push(-2);
+        let (source, target) = (Json::from(source), Json::from(target));
+        let patch = source.diff_with_options(&target, &DiffOptions::new().lcs_arrays(true));
+        assert_eq!(patch.operations().len(), 8);
+    }
+
+    proptest! {
+        #[test]
+        fn test_lcs_matches(old in vec(0..3i64, 0..12), new in vec(0..3i64, 0..12)) {
+            let (old, new): (Vec<Json>, Vec<Json>) =
+                (old.into_iter().map(Into::into).collect(), new.into_iter().map(Into::into).collect());
+            let mut matches = Vec::new();
+            lcs_matches(&old, &new, (0, 0), &mut matches);
+            prop_assert!(matches.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+            prop_assert!(matches.iter().all(|&(i, j)| old[i] == new[j]));
+            prop_assert_eq!(matches.len(), lcs_lengths(old.iter(), new.iter())[new.len()]);
+        }
+
+        #[test]
+        fn test_diff_apply(source in arb_json(), target in arb_json(), lcs: bool) {
+            let options = DiffOptions::new().lcs_arrays(lcs);
+            let patch = source.diff_with_options(&target, &options);
+            let mut patched = source.clone();
+            prop_assert_eq!(patched.apply_patch(&patch), Ok(()));
+            prop_assert_eq!(patched, target);
+        }
+
+        #[test]
+        fn test_diff_lcs_insert(source in vec(0..4i64, 0..8), index in any::<prop::sample::Index>(), value: i64) {
+            let mut target = source.clone();
+            target.insert(index.index(source.len() + 1), value);
+            let (source, target) = (Json::from(source), Json::from(target));
+            let patch = source.diff_with_options(&target, &DiffOptions::new().lcs_arrays(true));
+            prop_assert_eq!(patch.operations().len(), 1);
+        }
+    }
+}
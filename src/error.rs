@@ -0,0 +1,469 @@
+use itertools::Itertools;
+use nom::error::{ErrorKind, ParseError, VerboseError};
+use std::borrow::Cow;
+use std::fmt::{Display, Error, Formatter};
+
+/// An extension of nom's `ParseError` used by the JSON parser to report richer errors.
+///
+/// Every method has a default implementation that falls back to the plain `ParseError` methods,
+/// so any nom error type can be used with the parser. [`JsonError`](struct.JsonError.html)
+/// overrides them to keep track of expected tokens and the path to the error.
+pub trait JsonParseError<'a>: ParseError<&'a str> + Clone {
+    /// Creates an error from the input position and a [`JsonErrorKind`](enum.JsonErrorKind.html).
+    fn from_json_error_kind(input: &'a str, kind: JsonErrorKind) -> Self {
+        Self::from_error_kind(input, kind.into())
+    }
+
+    /// Creates an error from the input position and a description of the expected token.
+    fn from_expected(input: &'a str, _: &'static str) -> Self {
+        Self::from_json_error_kind(input, JsonErrorKind::Expected)
+    }
+
+    /// Records that the error occurred inside the array element at `index`.
+    fn in_index(self, _: usize) -> Self {
+        self
+    }
+
+    /// Records that the error occurred inside the value of the object member `key`.
+    fn in_key(self, _: Cow<'a, str>) -> Self {
+        self
+    }
+}
+
+impl<'a> JsonParseError<'a> for (&'a str, ErrorKind) {}
+
+impl<'a> JsonParseError<'a> for VerboseError<&'a str> {}
+
+/// The kind of a [`JsonError`](struct.JsonError.html).
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum JsonErrorKind {
+    /// One of the tokens listed by `JsonError::expected` was required at the error position.
+    Expected,
+    /// A number has a leading zero in its integer part, such as `007`.
+    LeadingZero,
+    /// An object contains the same key more than once, and duplicates are rejected by
+    /// `DuplicateKeys::Error`.
+    DuplicateKey,
+    /// Arrays and objects are nested deeper than `ParserOptions::max_depth` allows.
+    DepthLimitExceeded,
+    /// The input is longer than `ParserOptions::max_input_len` allows.
+    InputTooLong,
+    /// A string is longer than `ParserOptions::max_string_len` allows.
+    StringTooLong,
+    /// An array has more elements than `ParserOptions::max_array_len` allows.
+    ArrayTooLong,
+    /// An object has more members than `ParserOptions::max_object_len` allows.
+    ObjectTooLong,
+    /// A complete document was parsed, but more input follows it.
+    TrailingCharacters,
+    /// An error reported by one of nom's parsers.
+    Nom(ErrorKind),
+}
+
+impl From<JsonErrorKind> for ErrorKind {
+    fn from(kind: JsonErrorKind) -> Self {
+        match kind {
+            JsonErrorKind::Expected => ErrorKind::Tag,
+            JsonErrorKind::LeadingZero => ErrorKind::Digit,
+            JsonErrorKind::DuplicateKey => ErrorKind::Verify,
+            JsonErrorKind::DepthLimitExceeded
+            | JsonErrorKind::InputTooLong
+            | JsonErrorKind::StringTooLong
+            | JsonErrorKind::ArrayTooLong
+            | JsonErrorKind::ObjectTooLong => ErrorKind::TooLarge,
+            JsonErrorKind::TrailingCharacters => ErrorKind::Eof,
+            JsonErrorKind::Nom(kind) => kind,
+        }
+    }
+}
+
+/// A token that was expected at the position of a [`JsonError`](struct.JsonError.html).
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Expected {
+    /// A single character, such as `,` or `}`.
+    Char(char),
+    /// A description of a token, such as "a value".
+    Token(&'static str),
+}
+
+impl Display for Expected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            Expected::Char(c) => write!(f, "`{}`", c),
+            Expected::Token(token) => f.write_str(token),
+        }
+    }
+}
+
+/// A step on the path from the root JSON value to the position of an error.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum JsonContext<'a> {
+    /// Inside the array element at the given index.
+    Index(usize),
+    /// Inside the value of the object member with the given key.
+    Key(Cow<'a, str>),
+    /// A label attached with nom's `context` combinator.
+    Label(&'static str),
+}
+
+impl Display for JsonContext<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            JsonContext::Index(index) => write!(f, "array index {}", index),
+            JsonContext::Key(key) => write!(f, "key {:?}", key),
+            JsonContext::Label(label) => f.write_str(label),
+        }
+    }
+}
+
+/// A position in the source text.
+///
+/// `line` and `column` start at 1, and `column` is counted in characters.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Location {
+    /// The byte offset from the start of the source.
+    pub offset: usize,
+    /// The line number.
+    pub line: usize,
+    /// The column number.
+    pub column: usize,
+}
+
+impl Location {
+    pub(crate) fn new(source: &str, offset: usize) -> Self {
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+        Location {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+/// A JSON parse error.
+///
+/// The error records the remaining input at the failure position, the tokens that were expected
+/// there and the path to the value being parsed. Once the original source is attached with
+/// [`locate`](#method.locate), it also knows the line and column of the failure and its
+/// `Display` implementation renders the offending line with a caret under the error position.
+/// Tabs before the error are repeated in the caret line, while characters wider than one column,
+/// such as CJK characters or emoji, are padded with a single space.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{parse, Err, JsonError};
+///
+/// let source = "{\n    \"phone\": [1, 2 3]\n}";
+/// let err = match parse::<JsonError>(source) {
+///     Err(Err::Failure(err)) => err.locate(source),
+///     _ => unreachable!(),
+/// };
+/// let location = err.location().unwrap();
+/// assert_eq!((location.line, location.column), (2, 20));
+/// assert_eq!(
+///     err.to_string(),
+///     "expected `,` or `]` inside key \"phone\" at line 2, column 20\n  \
+///        |\n\
+///      2 |     \"phone\": [1, 2 3]\n  \
+///        |                    ^"
+/// );
+/// ```
+#[derive(PartialEq, Clone, Debug)]
+pub struct JsonError<'a> {
+    input: &'a str,
+    kind: JsonErrorKind,
+    expected: Vec<Expected>,
+    context: Vec<JsonContext<'a>>,
+    source: Option<&'a str>,
+}
+
+impl<'a> JsonError<'a> {
+    /// Creates an error at the position of `input`.
+    pub fn new(input: &'a str, kind: JsonErrorKind) -> Self {
+        JsonError {
+            input,
+            kind,
+            expected: Vec::new(),
+            context: Vec::new(),
+            source: None,
+        }
+    }
+
+    /// Attaches the original source to the error, so that its location can be computed.
+    ///
+    /// `source` must be the string that was passed to the parser. Otherwise the error is
+    /// returned unchanged.
+    pub fn locate(mut self, source: &'a str) -> Self {
+        let start = source.as_ptr() as usize;
+        let pos = self.input.as_ptr() as usize;
+        if start <= pos && pos + self.input.len() == start + source.len() {
+            self.source = Some(source);
+        }
+        self
+    }
+
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> JsonErrorKind {
+        self.kind
+    }
+
+    /// Returns the remaining input at the position of the error.
+    pub fn input(&self) -> &'a str {
+        self.input
+    }
+
+    /// Returns the tokens that were expected at the position of the error.
+    pub fn expected(&self) -> &[Expected] {
+        &self.expected
+    }
+
+    /// Returns the path to the error, from the innermost value to the outermost.
+    pub fn context(&self) -> &[JsonContext<'a>] {
+        &self.context
+    }
+
+    /// Returns the location of the error, if the source has been attached with
+    /// [`locate`](#method.locate).
+    pub fn location(&self) -> Option<Location> {
+        self.source
+            .map(|source| Location::new(source, source.len() - self.input.len()))
+    }
+
+    fn with_expected(input: &'a str, expected: Expected) -> Self {
+        let mut err = Self::new(input, JsonErrorKind::Expected);
+        err.expected.push(expected);
+        err
+    }
+}
+
+impl<'a> ParseError<&'a str> for JsonError<'a> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Self {
+        Self::new(input, JsonErrorKind::Nom(kind))
+    }
+
+    fn append(_: &'a str, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: &'a str, c: char) -> Self {
+        Self::with_expected(input, Expected::Char(c))
+    }
+
+    fn or(mut self, other: Self) -> Self {
+        if self.input.len() < other.input.len() {
+            return self;
+        } else if self.input.len() > other.input.len() {
+            return other;
+        }
+        match (self.kind, other.kind) {
+            (JsonErrorKind::Expected, JsonErrorKind::Expected) => {
+                for expected in other.expected {
+                    if !self.expected.contains(&expected) {
+                        self.expected.push(expected);
+                    }
+                }
+                self
+            }
+            (JsonErrorKind::Expected, _) => self,
+            _ => other,
+        }
+    }
+
+    fn add_context(_: &'a str, ctx: &'static str, mut other: Self) -> Self {
+        other.context.push(JsonContext::Label(ctx));
+        other
+    }
+}
+
+impl<'a> JsonParseError<'a> for JsonError<'a> {
+    fn from_json_error_kind(input: &'a str, kind: JsonErrorKind) -> Self {
+        Self::new(input, kind)
+    }
+
+    fn from_expected(input: &'a str, token: &'static str) -> Self {
+        Self::with_expected(input, Expected::Token(token))
+    }
+
+    fn in_index(mut self, index: usize) -> Self {
+        self.context.push(JsonContext::Index(index));
+        self
+    }
+
+    fn in_key(mut self, key: Cow<'a, str>) -> Self {
+        self.context.push(JsonContext::Key(key));
+        self
+    }
+}
+
+impl Display for JsonError<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self.kind {
+            JsonErrorKind::Expected => {
+                let (last, init) = self
+                    .expected
+                    .split_last()
+                    .unwrap_or((&Expected::Token("?"), &[]));
+                if init.is_empty() {
+                    write!(f, "expected {}", last)?;
+                } else {
+                    write!(f, "expected {} or {}", init.iter().format(", "), last)?;
+                }
+            }
+            JsonErrorKind::LeadingZero => {
+                f.write_str("leading zeros are not allowed in numbers")?
+            }
+            JsonErrorKind::DuplicateKey => f.write_str("duplicate key")?,
+            JsonErrorKind::DepthLimitExceeded => f.write_str("nesting too deep")?,
+            JsonErrorKind::InputTooLong => f.write_str("input too long")?,
+            JsonErrorKind::StringTooLong => f.write_str("string too long")?,
+            JsonErrorKind::ArrayTooLong => f.write_str("too many array elements")?,
+            JsonErrorKind::ObjectTooLong => f.write_str("too many object members")?,
+            JsonErrorKind::TrailingCharacters => f.write_str("trailing characters")?,
+            JsonErrorKind::Nom(kind) => write!(f, "invalid input ({})", kind.description())?,
+        };
+        if !self.context.is_empty() {
+            write!(f, " inside {}", self.context.iter().format(" of "))?;
+        }
+        match (self.source, self.location()) {
+            (Some(source), Some(location)) => {
+                let line_start = source[..location.offset]
+                    .rfind('\n')
+                    .map_or(0, |pos| pos + 1);
+                let line = source[line_start..].lines().next().unwrap_or("");
+                let number = location.line.to_string();
+                let gutter = " ".repeat(number.len());
+                // Tabs are copied so that the caret lines up with the rendered line.
+                let pad: String = source[line_start..location.offset]
+                    .chars()
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+                write!(
+                    f,
+                    " at line {}, column {}\n{} |\n{} | {}\n{} | {}^",
+                    location.line, location.column, gutter, number, line, gutter, pad
+                )
+            }
+            _ => match self.input.lines().next() {
+                Some(rest) if !rest.is_empty() => {
+                    write!(f, " at {:?}", rest.chars().take(16).collect::<String>())
+                }
+                _ => f.write_str(" at end of input"),
+            },
+        }
+    }
+}
+
+impl std::error::Error for JsonError<'_> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_json_element;
+    use nom::Err;
+
+    fn parse_err(source: &str) -> JsonError<'_> {
+        match parse_json_element::<JsonError>(source) {
+            Err(Err::Error(err)) | Err(Err::Failure(err)) => err.locate(source),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_location() {
+        let err = parse_err("{\n  \"a\": [\n    1,\n    tru\n  ]\n}");
+        assert_eq!(
+            err.location(),
+            Some(Location {
+                offset: 22,
+                line: 4,
+                column: 5
+            })
+        );
+        assert_eq!(err.input(), "tru\n  ]\n}");
+    }
+
+    #[test]
+    fn test_expected() {
+        let err = parse_err(r#"{"a": 1 "b": 2}"#);
+        assert_eq!(err.kind(), JsonErrorKind::Expected);
+        assert_eq!(err.expected(), &[Expected::Char(','), Expected::Char('}')]);
+
+        let err = parse_err("[");
+        assert_eq!(
+            err.expected(),
+            &[Expected::Token("a value"), Expected::Char(']')]
+        );
+
+        let err = parse_err(r#"{"a" 1}"#);
+        assert_eq!(err.expected(), &[Expected::Char(':')]);
+    }
+
+    #[test]
+    fn test_context() {
+        let err = parse_err(r#"{"name": "Frank", "phone": [{}, {}, {}, {"work": nul}]}"#);
+        assert_eq!(
+            err.context(),
+            &[
+                JsonContext::Key("work".into()),
+                JsonContext::Index(3),
+                JsonContext::Key("phone".into())
+            ]
+        );
+        assert_eq!(err.expected(), &[Expected::Token("a value")]);
+    }
+
+    #[test]
+    fn test_display() {
+        let err = parse_err("[1,\n\t2,\n\t]");
+        assert_eq!(
+            err.to_string(),
+            "expected a value inside array index 2 at line 3, column 2\n  |\n3 | \t]\n  | \t^"
+        );
+
+        let err = parse_err("");
+        assert_eq!(
+            err.to_string(),
+            "expected a value at line 1, column 1\n  |\n1 | \n  | ^"
+        );
+    }
+
+    #[test]
+    fn test_display_tabs() {
+        let err = parse_err("{\n\t\"a\":\t[1, x]}");
+        assert_eq!(
+            err.to_string(),
+            "expected a value inside array index 1 of key \"a\" at line 2, column 11\n  \
+               |\n\
+             2 | \t\"a\":\t[1, x]}\n  \
+               | \t    \t    ^"
+        );
+    }
+
+    #[test]
+    fn test_display_trailing() {
+        let err = crate::parser::parse_complete("{}\n\n  x").unwrap_err();
+        assert_eq!(err.kind(), JsonErrorKind::TrailingCharacters);
+        assert_eq!(
+            err.to_string(),
+            "trailing characters at line 3, column 3\n  |\n3 |   x\n  |   ^"
+        );
+    }
+
+    #[test]
+    fn test_display_unlocated() {
+        let err = JsonError::from_char("abc", '"');
+        assert_eq!(err.to_string(), "expected `\"` at \"abc\"");
+        let err = JsonError::from_error_kind("", ErrorKind::HexDigit);
+        assert_eq!(
+            err.to_string(),
+            "invalid input (Hexadecimal Digit) at end of input"
+        );
+    }
+
+    #[test]
+    fn test_locate_foreign_source() {
+        let err = JsonError::from_char("abc", '"').locate("xyz");
+        assert_eq!(err.location(), None);
+    }
+}
@@ -0,0 +1,296 @@
+use crate::repr::{Json, JsonValue};
+use crate::utils::PadAdapter;
+
+use itertools::Itertools;
+use std::fmt::{Display, Error, Formatter, Write};
+
+/// Displays a value as [JSON5](https://spec.json5.org), created by
+/// [`Json::json5`](struct.Json.html#method.json5).
+///
+/// Keys that are identifiers are not quoted, and strings are quoted with whichever of double or
+/// single quotes needs fewer escapes. Non-finite floats are written as `Infinity`, `-Infinity`
+/// and `NaN`. The alternate flag `{:#}` pretty-prints the value like the `Display` impl of
+/// `Json`.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{json, ParserOptions};
+///
+/// let json = json!({
+///     "name": "Frank's",
+///     "two words": [1.5, f64::INFINITY, null],
+/// });
+/// let json5 = json.json5().to_string();
+/// assert_eq!(json5, r#"{name: "Frank's", "two words": [1.5, Infinity, null]}"#);
+///
+/// let parser = ParserOptions::new().json5(true).build();
+/// assert_eq!(parser.parse_complete(&json5).unwrap(), json);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Json5Display<'j, 'a> {
+    json: &'j Json<'a>,
+}
+
+impl<'a> Json<'a> {
+    /// Returns a value that displays `self` as JSON5.
+    pub fn json5(&self) -> Json5Display<'_, 'a> {
+        Json5Display { json: self }
+    }
+}
+
+impl Display for Json5Display<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let value = match self.json.as_value() {
+            Some(value) => value,
+            None => return f.write_str("null"),
+        };
+        match value {
+            JsonValue::Float(float) if float.is_nan() => f.write_str("NaN"),
+            JsonValue::Float(float) if float.is_infinite() => f.write_str(if *float > 0.0 {
+                "Infinity"
+            } else {
+                "-Infinity"
+            }),
+            JsonValue::String(string) => write_string(f, string),
+            JsonValue::Object(obj) if obj.is_empty() => f.write_str("{}"),
+            JsonValue::Object(obj) => {
+                if f.alternate() {
+                    let mut f: PadAdapter = f.into();
+                    f.write_str("{\n")?;
+                    let formatter = obj.iter().format_with(",\n", |(k, v), f| {
+                        f(&format_args!("{}: {:#}", Key(k), v.json5()))
+                    });
+                    f.write_fmt(format_args!("{}", formatter))?;
+                    f.into_inner().write_str("\n}")
+                } else {
+                    let formatter = obj.iter().format_with(", ", |(k, v), f| {
+                        f(&format_args!("{}: {}", Key(k), v.json5()))
+                    });
+                    write!(f, "{{{}}}", formatter)
+                }
+            }
+            JsonValue::Array(arr) if arr.is_empty() => f.write_str("[]"),
+            JsonValue::Array(arr) => {
+                if f.alternate() {
+                    let mut f: PadAdapter = f.into();
+                    f.write_str("[\n")?;
+                    let formatter = arr
+                        .iter()
+                        .format_with(",\n", |elem, f| f(&format_args!("{:#}", elem.json5())));
+                    f.write_fmt(format_args!("{}", formatter))?;
+                    f.into_inner().write_str("\n]")
+                } else {
+                    let formatter = arr.iter().format_with(", ", |elem, f| f(&elem.json5()));
+                    write!(f, "[{}]", formatter)
+                }
+            }
+            value => value.fmt(f),
+        }
+    }
+}
+
+/// An object key, displayed without quotes if it is an identifier.
+struct Key<'k>(&'k str);
+
+impl Display for Key<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let mut chars = self.0.chars();
+        let is_start = |c: char| c.is_alphabetic() || matches!(c, '$' | '_');
+        let is_identifier = chars.next().is_some_and(is_start)
+            && chars.all(|c| is_start(c) || c.is_numeric() || matches!(c, '\u{200c}' | '\u{200d}'));
+        if is_identifier {
+            f.write_str(self.0)
+        } else {
+            write_string(f, self.0)
+        }
+    }
+}
+
+/// Writes a quoted and escaped string, using single quotes if it contains more double quotes than
+/// single quotes.
+fn write_string(f: &mut Formatter<'_>, string: &str) -> Result<(), Error> {
+    let count = |quote| string.chars().filter(|&c| c == quote).count();
+    let quote = if count('"') > count('\'') { '\'' } else { '"' };
+    f.write_char(quote)?;
+    for c in string.chars() {
+        match c {
+            '\\' => f.write_str(r"\\")?,
+            '\x08' => f.write_str(r"\b")?,
+            '\x0c' => f.write_str(r"\f")?,
+            '\n' => f.write_str(r"\n")?,
+            '\r' => f.write_str(r"\r")?,
+            '\t' => f.write_str(r"\t")?,
+            '\u{2028}' => f.write_str(r"\u2028")?,
+            '\u{2029}' => f.write_str(r"\u2029")?,
+            c if c == quote => write!(f, "\\{}", c)?,
+            c if c.is_control() && (c as u32) < 0x100 => write!(f, "\\x{:02x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char(quote)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::{JsonError, JsonErrorKind};
+    use crate::options::ParserOptions;
+    use crate::repr::Json;
+
+    fn parse(input: &str) -> Result<Json<'_>, JsonError<'_>> {
+        ParserOptions::new()
+            .json5(true)
+            .build()
+            .parse_complete(input)
+    }
+
+    fn assert_invalid(input: &str) {
+        assert!(parse(input).is_err(), "{:?} should be rejected", input);
+    }
+
+    #[test]
+    fn test_spec_example() {
+        let input = r#"// comments
+{
+  unquoted: 'and you can quote me on that',
+  singleQuotes: 'I can use "double quotes" here',
+  lineBreaks: "Look, Mom! \
+No \\n's!",
+  hexadecimal: 0xdecaf,
+  leadingDecimalPoint: .8675309, andTrailing: 8675309.,
+  positiveSign: +1,
+  trailingComma: 'in objects', andIn: ['arrays',],
+  "backwardsCompatible": "with JSON",
+}
+"#;
+        assert_eq!(
+            parse(input).unwrap(),
+            json!({
+                "unquoted": "and you can quote me on that",
+                "singleQuotes": "I can use \"double quotes\" here",
+                "lineBreaks": "Look, Mom! No \\n's!",
+                "hexadecimal": 0xdecaf,
+                "leadingDecimalPoint": 0.8675309,
+                "andTrailing": 8675309.0,
+                "positiveSign": 1,
+                "trailingComma": "in objects",
+                "andIn": ["arrays"],
+                "backwardsCompatible": "with JSON",
+            })
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        let number = |input| parse(input).unwrap();
+        assert_eq!(number("0x1F"), Json::from(31));
+        assert_eq!(number("-0XfF"), Json::from(-255));
+        assert_eq!(number("-0x8000000000000000"), Json::from(i64::MIN));
+        assert_eq!(number("0xFFFFFFFFFFFFFFFF"), Json::from(u64::MAX));
+        assert_eq!(number("0x10000000000000000"), Json::from(2f64.powi(64)));
+        assert_eq!(number("+Infinity"), Json::from(f64::INFINITY));
+        assert_eq!(number("-Infinity"), Json::from(f64::NEG_INFINITY));
+        assert!(number("NaN").as_f64().unwrap().is_nan());
+        assert_eq!(number("+.5e1"), Json::from(5.0));
+        assert_eq!(number("-5."), Json::from(-5.0));
+        assert_eq!(number("1e3"), Json::from(1000.0));
+
+        let lossless = ParserOptions::new().json5(true).lossless_numbers(true);
+        let parser = lossless.build();
+        let lexeme = |input| parser.parse_complete(input).unwrap().to_string();
+        assert_eq!(lexeme("+1"), "1");
+        assert_eq!(lexeme("-.5"), "-0.5");
+        assert_eq!(lexeme("2.E-3"), "2.0E-3");
+        assert_eq!(lexeme("12.5"), "12.5");
+
+        for input in &[
+            "01", "0x", "0xG", ".", "+", "-.e1", "1.e", "infinity", "+NaNa", "1_000",
+        ] {
+            assert_invalid(input);
+        }
+    }
+
+    #[test]
+    fn test_strings() {
+        let string = |input| parse(input).unwrap();
+        assert_eq!(string(r#"'"\''"#), Json::from("\"'"));
+        assert_eq!(string(r"'\x41\u0042\v\0\a'"), Json::from("AB\x0b\0a"));
+        assert_eq!(string(r"'\ud83d\ude00'"), Json::from("\u{1f600}"));
+        assert_eq!(string("'a\\\r\nb\\\u{2028}c'"), Json::from("abc"));
+        assert_eq!(string("'\u{2028}'"), Json::from("\u{2028}"));
+
+        for input in &[
+            "'a",
+            "'a\nb'",
+            r"'\1'",
+            r"'\01'",
+            r"'\x4'",
+            r"'\u12'",
+            r"'\ud800'",
+            "\"a'",
+        ] {
+            assert_invalid(input);
+        }
+    }
+
+    #[test]
+    fn test_keys_and_comments() {
+        let input = "/* leading */ {$a: 1, _b2: 2, \\u0063: 3, ünï: 4, 'd e': 5 // line\n} // end";
+        assert_eq!(
+            parse(input).unwrap(),
+            json!({"$a": 1, "_b2": 2, "c": 3, "ünï": 4, "d e": 5})
+        );
+        assert_eq!(
+            parse("\u{feff}[\u{a0}1\u{2028},\u{3000}2\x0b]").unwrap(),
+            json!([1, 2])
+        );
+
+        for input in &[
+            "{1a: 1}", "{a b: 1}", "{a-b: 1}", "[1,,]", "[,]", "{,}", "{a: 1,,}",
+        ] {
+            assert_invalid(input);
+        }
+        let err = parse("[1, /* unterminated").unwrap_err();
+        assert_eq!(err.kind(), JsonErrorKind::Expected);
+        assert_eq!(err.location().unwrap().column, 20);
+    }
+
+    #[test]
+    fn test_json_mode_unchanged() {
+        let parser = ParserOptions::new().build();
+        for input in &[
+            "[1,]",
+            "{a: 1}",
+            "'a'",
+            "+1",
+            "0x1",
+            "NaN",
+            "[1] // comment",
+        ] {
+            assert!(parser.parse_complete(input).is_err());
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let json = json!({
+            "a": [1, -2.5, f64::NAN, f64::NEG_INFINITY, "'\"\u{2028}\u{1}"],
+            "": {"é1": true, "1é": null, "x y": {}},
+            "b": [],
+        });
+        assert_eq!(
+            json.json5().to_string(),
+            r#"{a: [1, -2.5, NaN, -Infinity, "'\"\u2028\x01"], "": {é1: true, "1é": null, "x y": {}}, b: []}"#
+        );
+        assert_eq!(
+            format!("{:#}", json!({"a": [1, {"b": 'c'.to_string()}], "c": []}).json5()),
+            "{\n    a: [\n        1,\n        {\n            b: \"c\"\n        }\n    ],\n    c: []\n}"
+        );
+
+        let round_trip = json!({"k": ["\\'\"", 0.1, u64::MAX, f64::INFINITY, {"'": "\t"}]});
+        assert_eq!(parse(&round_trip.json5().to_string()).unwrap(), round_trip);
+        assert_eq!(
+            parse(&format!("{:#}", round_trip.json5())).unwrap(),
+            round_trip
+        );
+    }
+}
@@ -1,13 +1,54 @@
+#[macro_use]
+mod macros;
+
+mod convert;
+mod cst;
+#[cfg(feature = "serde")]
+mod de;
+mod diff;
+mod error;
+mod json5;
+mod map;
+mod merge;
+mod number;
+mod options;
 mod parser;
+mod patch;
+mod path;
+mod pointer;
 mod repr;
+#[cfg(feature = "serde")]
+mod ser;
+mod spans;
 mod utils;
 
+pub use convert::{ConversionError, ConversionErrorKind, FromJson, ToJson};
+pub use cst::{Cst, CstArray, CstElement, CstMember, CstObject, CstValue, Token};
+#[cfg(feature = "serde")]
+pub use de::{from_json, from_str, Deserializer, SerdeError, SerdeErrorKind};
+pub use diff::{DiffDisplay, DiffOptions};
+pub use error::{Expected, JsonContext, JsonError, JsonErrorKind, JsonParseError, Location};
+pub use json5::Json5Display;
+pub use map::Map;
 pub use nom::{
     error::{ErrorKind, ParseError},
     Err, IResult,
 };
-pub use parser::parse_json_element as parse;
+#[cfg(feature = "derive")]
+pub use nom_json_parser_derive::{FromJson, ToJson};
+pub use number::Number;
+pub use options::{DuplicateKeys, ParserOptions};
+pub use parser::{
+    parse_complete, parse_cst, parse_json_element as parse, parse_with_options, parse_with_spans,
+    Parser,
+};
+pub use patch::{Patch, PatchError, PatchErrorKind, PatchOperation};
+pub use path::JsonPath;
+pub use pointer::{JsonPointer, PointerError};
 pub use repr::{Json, JsonValue};
+#[cfg(feature = "serde")]
+pub use ser::{to_json, to_string, to_writer};
+pub use spans::{Span, Spans};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,152 @@
+/// Constructs a [`Json`](struct.Json.html) value from JSON-like syntax.
+///
+/// Object keys and interpolated values can be any Rust expressions. Keys must convert into
+/// `Cow<str>`, and values into `Json` through the `From` impls of `Json`. An expression that
+/// spans several tokens, such as `a + 1`, only needs parentheses when used as a key.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{json, Json};
+///
+/// let x = 1.5;
+/// let name = String::from("Alice");
+/// let json = json!({
+///     "name": name,
+///     "age": 20,
+///     "tags": [1, 2, null],
+///     "nested": {"x": x, "y": -x * 2.0},
+///     "flags": [true, false, {}, []],
+/// });
+/// assert_eq!(
+///     json.to_string(),
+///     r#"{"name": "Alice", "age": 20, "tags": [1, 2, null], "nested": {"x": 1.5, "y": -3.0}, "flags": [true, false, {}, []]}"#
+/// );
+/// assert_eq!(json!(null), Json::from(None));
+/// ```
+#[macro_export]
+macro_rules! json {
+    (null) => {
+        $crate::Json::from(None)
+    };
+    ([]) => {
+        $crate::Json::from($crate::JsonValue::Array(vec![]))
+    };
+    ([ $($tt:tt)+ ]) => {
+        $crate::Json::from($crate::JsonValue::Array($crate::json!(@array [] $($tt)+)))
+    };
+    ({}) => {
+        $crate::Json::from($crate::Map::new())
+    };
+    ({ $($tt:tt)+ }) => {{
+        let mut object = $crate::Map::new();
+        $crate::json!(@object object () ($($tt)+));
+        $crate::Json::from(object)
+    }};
+    ($other:expr) => {
+        $crate::Json::from($other)
+    };
+
+    // Array elements, accumulated as expressions in the brackets.
+    (@array [$($elems:expr,)*]) => {
+        vec![$($elems,)*]
+    };
+    (@array [$($elems:expr),*]) => {
+        vec![$($elems),*]
+    };
+    (@array [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!(null)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] [$($array:tt)*] $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!([$($array)*])] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] {$($object:tt)*} $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!({$($object)*})] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!($next),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $last:expr) => {
+        $crate::json!(@array [$($elems,)* $crate::json!($last)])
+    };
+    (@array [$($elems:expr),*] , $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)*] $($rest)*)
+    };
+
+    // Object members, inserted into `$object` one at a time. The key is accumulated in
+    // parentheses until the colon, then the value is parsed and inserted.
+    (@object $object:ident () ()) => {};
+    (@object $object:ident [$($key:tt)+] ($value:expr) , $($rest:tt)*) => {
+        let _ = $object.insert(($($key)+), $value);
+        $crate::json!(@object $object () ($($rest)*));
+    };
+    (@object $object:ident [$($key:tt)+] ($value:expr)) => {
+        let _ = $object.insert(($($key)+), $value);
+    };
+    (@object $object:ident ($($key:tt)+) (: null $($rest:tt)*)) => {
+        $crate::json!(@object $object [$($key)+] ($crate::json!(null)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: [$($array:tt)*] $($rest:tt)*)) => {
+        $crate::json!(@object $object [$($key)+] ($crate::json!([$($array)*])) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: {$($map:tt)*} $($rest:tt)*)) => {
+        $crate::json!(@object $object [$($key)+] ($crate::json!({$($map)*})) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: $value:expr , $($rest:tt)*)) => {
+        $crate::json!(@object $object [$($key)+] ($crate::json!($value)) , $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: $value:expr)) => {
+        $crate::json!(@object $object [$($key)+] ($crate::json!($value)));
+    };
+    (@object $object:ident ($($key:tt)*) ($tt:tt $($rest:tt)*)) => {
+        $crate::json!(@object $object ($($key)* $tt) ($($rest)*));
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::map::Map;
+    use crate::parser::parse_complete;
+    use crate::repr::Json;
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(json!(null), Json(None));
+        assert_eq!(json!(true), Json::from(true));
+        assert_eq!(json!(-1), Json::from(-1));
+        assert_eq!(json!(u64::MAX), Json::from(u64::MAX));
+        assert_eq!(json!("a"), Json::from("a"));
+        assert_eq!(json!([]), Json::from(Vec::<Json>::new()));
+        assert_eq!(json!({}), Json::from(Map::new()));
+    }
+
+    #[test]
+    fn test_nested() {
+        let source =
+            r#"{"a": [1, [null, {}], {"b": [true, "c"]}], "d": {"e": null, "f": -2.5}, "g": []}"#;
+        let json = json!({
+            "a": [1, [null, {}], {"b": [true, "c"]}],
+            "d": {"e": null, "f": -2.5},
+            "g": [],
+        });
+        assert_eq!(json, parse_complete(source).unwrap());
+        assert_eq!(json.to_string(), source);
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let key = "k".to_string();
+        let values = vec![1, 2];
+        let nested = json!({"x": 1});
+        let json = json!({
+            key.clone(): values.len() + 1,
+            (format!("{}2", key)): values,
+            "nested": nested.clone(),
+            "option": Some(3).map_or(json!(null), Json::from),
+            "list": [key, nested, 1 + 1,],
+        });
+        assert_eq!(
+            json.to_string(),
+            r#"{"k": 3, "k2": [1, 2], "nested": {"x": 1}, "option": 3, "list": ["k", {"x": 1}, 2]}"#
+        );
+    }
+}
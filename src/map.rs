@@ -0,0 +1,207 @@
+use crate::repr::Json;
+
+use indexmap::map::{IntoIter, Iter, IterMut, Keys, Values, ValuesMut};
+use indexmap::IndexMap;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+
+/// A JSON object that keeps its members in insertion order.
+///
+/// Parsing an object keeps the members in the order they appear in the source, so printing it
+/// back does not reorder keys. Lookups by key are hash-based.
+///
+/// Two maps are equal if they contain the same members, regardless of order. They are compared
+/// like sorted maps, member by member in key order.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{Json, Map};
+///
+/// let mut map = Map::new();
+/// map.insert("name", "Alice");
+/// map.insert("age", 20);
+/// assert_eq!(map.get("age"), Some(&Json::from(20)));
+/// assert_eq!(Json::from(map).to_string(), r#"{"name": "Alice", "age": 20}"#);
+/// ```
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Map<'a>(pub(crate) IndexMap<Cow<'a, str>, Json<'a>>);
+
+impl<'a> Map<'a> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty map with space for at least `capacity` members.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Map(IndexMap::with_capacity(capacity))
+    }
+
+    /// Returns the number of members in the map.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the map has no members.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns `true` if the map contains a member with the given key.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+
+    /// Returns a reference to the value of the member with the given key.
+    pub fn get(&self, key: &str) -> Option<&Json<'a>> {
+        self.0.get(key)
+    }
+
+    /// Returns a mutable reference to the value of the member with the given key.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Json<'a>> {
+        self.0.get_mut(key)
+    }
+
+    /// Inserts a member and returns the previous value of the key, if any.
+    ///
+    /// A new key is appended at the end, while an existing key keeps its position.
+    pub fn insert<K: Into<Cow<'a, str>>, V: Into<Json<'a>>>(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> Option<Json<'a>> {
+        self.0.insert(key.into(), value.into())
+    }
+
+    /// Removes a member and returns its value, if any. The order of the other members is
+    /// preserved.
+    pub fn remove(&mut self, key: &str) -> Option<Json<'a>> {
+        self.0.shift_remove(key)
+    }
+
+    /// Returns an iterator over the members in order.
+    pub fn iter(&self) -> Iter<'_, Cow<'a, str>, Json<'a>> {
+        self.0.iter()
+    }
+
+    /// Returns an iterator over the members in order, with mutable references to the values.
+    pub fn iter_mut(&mut self) -> IterMut<'_, Cow<'a, str>, Json<'a>> {
+        self.0.iter_mut()
+    }
+
+    /// Returns an iterator over the keys in order.
+    pub fn keys(&self) -> Keys<'_, Cow<'a, str>, Json<'a>> {
+        self.0.keys()
+    }
+
+    /// Returns an iterator over the values in order.
+    pub fn values(&self) -> Values<'_, Cow<'a, str>, Json<'a>> {
+        self.0.values()
+    }
+
+    /// Returns an iterator over mutable references to the values in order.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, Cow<'a, str>, Json<'a>> {
+        self.0.values_mut()
+    }
+
+    /// Converts the map into one that owns all of its keys and values.
+    pub fn into_owned(self) -> Map<'static> {
+        Map(self
+            .0
+            .into_iter()
+            .map(|(key, value)| (Cow::Owned(key.into_owned()), value.into_owned()))
+            .collect())
+    }
+
+    /// Returns a map that borrows all of its keys and values from `self`. See
+    /// [`Json::borrow`](struct.Json.html#method.borrow).
+    pub fn borrow(&self) -> Map<'_> {
+        Map(self
+            .0
+            .iter()
+            .map(|(key, value)| (Cow::Borrowed(&**key), value.borrow()))
+            .collect())
+    }
+
+    fn sorted(&self) -> Vec<(&Cow<'a, str>, &Json<'a>)> {
+        let mut members = self.0.iter().collect::<Vec<_>>();
+        members.sort_by_key(|&(key, _)| key);
+        members
+    }
+}
+
+impl PartialOrd for Map<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.sorted().partial_cmp(&other.sorted())
+    }
+}
+
+impl<'a, K: Into<Cow<'a, str>>, V: Into<Json<'a>>> FromIterator<(K, V)> for Map<'a> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Map(iter
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect())
+    }
+}
+
+impl<'a, K: Into<Cow<'a, str>>, V: Into<Json<'a>>> Extend<(K, V)> for Map<'a> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        self.0
+            .extend(iter.into_iter().map(|(k, v)| (k.into(), v.into())))
+    }
+}
+
+impl<'a> IntoIterator for Map<'a> {
+    type Item = (Cow<'a, str>, Json<'a>);
+    type IntoIter = IntoIter<Cow<'a, str>, Json<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, 'b> IntoIterator for &'b Map<'a> {
+    type Item = (&'b Cow<'a, str>, &'b Json<'a>);
+    type IntoIter = Iter<'b, Cow<'a, str>, Json<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<'a, 'b> IntoIterator for &'b mut Map<'a> {
+    type Item = (&'b Cow<'a, str>, &'b mut Json<'a>);
+    type IntoIter = IterMut<'b, Cow<'a, str>, Json<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_insertion_order() {
+        let mut map: Map = vec![("b", 1), ("a", 2), ("c", 3)].into_iter().collect();
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec!["b", "a", "c"]);
+        assert_eq!(map.insert("a", 4), Some(Json::from(2)));
+        assert_eq!(map.remove("b"), Some(Json::from(1)));
+        map.insert("b", 5);
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec!["a", "c", "b"]);
+    }
+
+    #[test]
+    fn test_comparison() {
+        let lhs: Map = vec![("b", 1), ("a", 2)].into_iter().collect();
+        let rhs: Map = vec![("a", 2), ("b", 1)].into_iter().collect();
+        assert_eq!(lhs, rhs);
+        assert_eq!(lhs.partial_cmp(&rhs), Some(Ordering::Equal));
+
+        let greater: Map = vec![("a", 3)].into_iter().collect();
+        assert!(lhs < greater);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::map::Map;
+use crate::repr::{Json, JsonValue};
+
+impl<'a> Json<'a> {
+    /// Applies a JSON Merge Patch as defined in RFC 7396.
+    ///
+    /// An object patch is merged into the target member by member, recursively: a `null` member
+    /// removes the key, and any other member is merged into the value of the key, which is created
+    /// if missing. A target that is not an object is replaced by an empty object first. Any other
+    /// patch replaces the target.
+    ///
+    /// # Example
+    /// ```rust
+    /// use nom_json_parser::parse_complete;
+    ///
+    /// let mut settings =
+    ///     parse_complete(r#"{"theme": "light", "font": {"size": 12, "family": "mono"}}"#).unwrap();
+    /// let overrides = parse_complete(r#"{"theme": null, "font": {"size": 14}}"#).unwrap();
+    /// settings.merge_patch(&overrides);
+    /// assert_eq!(settings.to_string(), r#"{"font": {"size": 14, "family": "mono"}}"#);
+    /// ```
+    pub fn merge_patch(&mut self, patch: &Json<'a>) {
+        let patch = match patch.as_object() {
+            Some(patch) => patch,
+            None => {
+                *self = patch.clone();
+                return;
+            }
+        };
+        if self.as_object().is_none() {
+            *self = Map::new().into();
+        }
+        let target = self.as_object_mut().unwrap();
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else if let Some(member) = target.get_mut(key) {
+                member.merge_patch(value);
+            } else {
+                let mut member = Json(None);
+                member.merge_patch(value);
+                target.insert(key.clone(), member);
+            }
+        }
+    }
+
+    /// Returns a merge patch that turns `source` into `target` when applied with
+    /// [`merge_patch`](#method.merge_patch).
+    ///
+    /// Only members that differ appear in the patch, and objects present on both sides are
+    /// diffed recursively. Any other changed value, including arrays, is replaced as a whole.
+    ///
+    /// Merge patches cannot set a member to `null`, so `null` members of objects in `target` are
+    /// dropped when the patch is applied.
+    ///
+    /// # Example
+    /// ```rust
+    /// use nom_json_parser::{parse_complete, Json};
+    ///
+    /// let defaults = parse_complete(r#"{"a": 1, "b": {"c": [1], "d": true}, "e": "x"}"#).unwrap();
+    /// let user = parse_complete(r#"{"a": 1, "b": {"c": [1, 2], "d": true}, "f": 0}"#).unwrap();
+    /// let patch = Json::diff_merge_patch(&defaults, &user);
+    /// assert_eq!(patch.to_string(), r#"{"e": null, "b": {"c": [1, 2]}, "f": 0}"#);
+    ///
+    /// let mut merged = defaults.clone();
+    /// merged.merge_patch(&patch);
+    /// assert_eq!(merged, user);
+    /// ```
+    pub fn diff_merge_patch(source: &Json<'a>, target: &Json<'a>) -> Json<'a> {
+        let (source, target) = match (source.as_object(), target.as_object()) {
+            (Some(source), Some(target)) => (source, target),
+            _ => return target.clone(),
+        };
+        let removed = source
+            .keys()
+            .filter(|key| !target.contains_key(key))
+            .map(|key| (key.clone(), Json(None)));
+        let changed = target
+            .iter()
+            .filter_map(|(key, value)| match source.get(key) {
+                Some(old) if old == value => None,
+                Some(old) => Some((key.clone(), Json::diff_merge_patch(old, value))),
+                None => Some((key.clone(), value.clone())),
+            });
+        JsonValue::Object(removed.chain(changed).collect()).into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::parse_complete;
+    use crate::repr::Json;
+
+    #[test]
+    fn test_rfc_examples() {
+        let cases = [
+            (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a": "c"}"#),
+            (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a": "b", "b": "c"}"#),
+            (r#"{"a":"b"}"#, r#"{"a":null}"#, "{}"),
+            (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b": "c"}"#),
+            (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a": "c"}"#),
+            (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a": ["b"]}"#),
+            (
+                r#"{"a": {"b": "c"}}"#,
+                r#"{"a": {"b": "d", "c": null}}"#,
+                r#"{"a": {"b": "d"}}"#,
+            ),
+            (r#"{"a": [{"b":"c"}]}"#, r#"{"a": [1]}"#, r#"{"a": [1]}"#),
+            (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c", "d"]"#),
+            (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+            (r#"{"a":"foo"}"#, "null", "null"),
+            (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+            (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e": null, "a": 1}"#),
+            (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a": "b"}"#),
+            ("{}", r#"{"a":{"bb":{"ccc":null}}}"#, r#"{"a": {"bb": {}}}"#),
+        ];
+        for (target, patch, expected) in cases.iter() {
+            let mut json = parse_complete(target).unwrap();
+            json.merge_patch(&parse_complete(patch).unwrap());
+            assert_eq!(json.to_string(), *expected, "{} + {}", target, patch);
+        }
+    }
+
+    #[test]
+    fn test_diff_merge_patch() {
+        let cases = [
+            ("{}", "{}", "{}"),
+            (r#"{"a": [1]}"#, r#"{"a": [1]}"#, "{}"),
+            (
+                r#"{"a": 1, "b": 2}"#,
+                r#"{"b": 3}"#,
+                r#"{"a": null, "b": 3}"#,
+            ),
+            (
+                r#"{"a": {"b": {"c": 1}}}"#,
+                r#"{"a": {"b": {"c": 1, "d": 2}}}"#,
+                r#"{"a": {"b": {"d": 2}}}"#,
+            ),
+            (r#"{"a": {"b": 1}}"#, r#"{"a": 1}"#, r#"{"a": 1}"#),
+            (r#"{"a": 1}"#, "[1]", "[1]"),
+            ("true", r#"{"a": 1}"#, r#"{"a": 1}"#),
+            ("true", "null", "null"),
+        ];
+        for (source, target, expected) in cases.iter() {
+            let source = parse_complete(source).unwrap();
+            let target = parse_complete(target).unwrap();
+            let patch = Json::diff_merge_patch(&source, &target);
+            assert_eq!(patch.to_string(), *expected);
+
+            let mut merged = source.clone();
+            merged.merge_patch(&patch);
+            assert_eq!(merged, target);
+        }
+    }
+}
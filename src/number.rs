@@ -0,0 +1,161 @@
+use crate::parser::recognize_number;
+
+use nom::combinator::all_consuming;
+use nom::error::ErrorKind;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt::{Display, Error, Formatter};
+
+/// A JSON number that keeps its source text.
+///
+/// Numbers are only parsed into this representation when
+/// [`ParserOptions::lossless_numbers`](struct.ParserOptions.html#method.lossless_numbers) is
+/// enabled. The original lexeme is preserved exactly, so integers of any size and decimals of any
+/// precision survive a parse/print round trip. Conversions to Rust numbers are checked.
+///
+/// Integer accessors such as [`as_u64`](#method.as_u64) only succeed for numbers without a
+/// fraction or exponent, following the same rule the parser uses to choose between
+/// `JsonValue::Int` and `JsonValue::Float`.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::Number;
+///
+/// let id = Number::new("18446744073709551615").unwrap();
+/// assert_eq!(id.as_u64(), Some(u64::MAX));
+/// assert_eq!(id.as_i64(), None);
+///
+/// let decimal = Number::new("0.1000000000000000055").unwrap();
+/// assert_eq!(decimal.to_string(), "0.1000000000000000055");
+/// assert_eq!(decimal.as_f64(), Some(0.1));
+///
+/// assert_eq!(Number::new("007"), None);
+/// ```
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct Number<'a>(Cow<'a, str>);
+
+impl<'a> Number<'a> {
+    /// Creates a number from its textual representation, or returns `None` if `lexeme` is not a
+    /// valid JSON number.
+    pub fn new<S: Into<Cow<'a, str>>>(lexeme: S) -> Option<Self> {
+        let lexeme = lexeme.into();
+        all_consuming(recognize_number::<(&str, ErrorKind)>)(&lexeme).ok()?;
+        Some(Number(lexeme))
+    }
+
+    /// Creates a number from a lexeme that is already known to be valid.
+    pub(crate) fn from_lexeme<S: Into<Cow<'a, str>>>(lexeme: S) -> Self {
+        Number(lexeme.into())
+    }
+
+    /// Returns the source text of the number.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Converts the number into one that owns its source text.
+    pub fn into_owned(self) -> Number<'static> {
+        Number(Cow::Owned(self.0.into_owned()))
+    }
+
+    /// Returns a number that borrows its source text from `self`.
+    pub fn borrow(&self) -> Number<'_> {
+        Number(Cow::Borrowed(&self.0))
+    }
+
+    /// Returns `true` if the number has neither a fraction nor an exponent.
+    pub fn is_integer(&self) -> bool {
+        !self.0.contains(['.', 'e', 'E'].as_ref())
+    }
+
+    /// Returns the number as an `i64`, if it is an integer in range.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_int()
+    }
+
+    /// Returns the number as a `u64`, if it is a non-negative integer in range.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_int()
+    }
+
+    /// Returns the number as an `i128`, if it is an integer in range.
+    pub fn as_i128(&self) -> Option<i128> {
+        self.as_int()
+    }
+
+    /// Returns the number as an `f64`, if it is finite at that precision.
+    ///
+    /// The result is rounded to the nearest representable value.
+    pub fn as_f64(&self) -> Option<f64> {
+        self.0.parse::<f64>().ok().filter(|f| f.is_finite())
+    }
+
+    fn as_int<T: std::str::FromStr>(&self) -> Option<T> {
+        if self.is_integer() {
+            self.0.parse().ok()
+        } else {
+            None
+        }
+    }
+}
+
+impl PartialOrd for Number<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.0 == other.0 {
+            return Some(Ordering::Equal);
+        }
+        let lhs = self.0.parse::<f64>().ok()?;
+        let rhs = other.0.parse::<f64>().ok()?;
+        // numbers with different lexemes are never equal, so ties are broken by the text
+        lhs.partial_cmp(&rhs)
+            .map(|ordering| ordering.then_with(|| self.0.cmp(&other.0)))
+    }
+}
+
+impl Display for Number<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        assert!(Number::new("-0.5e+10").is_some());
+        assert!(Number::new(String::from("12")).is_some());
+        assert_eq!(Number::new("1 "), None);
+        assert_eq!(Number::new("+1"), None);
+        assert_eq!(Number::new(""), None);
+    }
+
+    #[test]
+    fn test_accessors() {
+        let big = Number::new("170141183460469231731687303715884105727").unwrap();
+        assert_eq!(big.as_i128(), Some(i128::MAX));
+        assert_eq!(big.as_u64(), None);
+        assert_eq!(big.as_f64(), Some(1.7014118346046923e38));
+
+        let negative = Number::new("-1").unwrap();
+        assert_eq!(negative.as_i64(), Some(-1));
+        assert_eq!(negative.as_u64(), None);
+
+        let exp = Number::new("1E2").unwrap();
+        assert!(!exp.is_integer());
+        assert_eq!(exp.as_i64(), None);
+        assert_eq!(exp.as_f64(), Some(100.0));
+
+        assert_eq!(Number::new("1e400").unwrap().as_f64(), None);
+    }
+
+    #[test]
+    fn test_ordering() {
+        let num = |s| Number::new(s).unwrap();
+        assert!(num("2") < num("10"));
+        assert!(num("-1.5") < num("-1"));
+        assert!(num("1.0") != num("1"));
+        assert_ne!(num("1.0").partial_cmp(&num("1")), Some(Ordering::Equal));
+    }
+}
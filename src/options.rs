@@ -0,0 +1,231 @@
+use crate::parser::Parser;
+use crate::utils::Whitespace;
+
+/// Options controlling how JSON text is parsed.
+///
+/// The default options parse standard JSON as described in RFC 8259.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{parse_with_options, Json, JsonValue, Number, ParserOptions};
+/// use nom::error::ErrorKind;
+/// type E<'a> = (&'a str, ErrorKind);
+///
+/// let options = ParserOptions::new().lossless_numbers(true);
+/// let (_, json) = parse_with_options::<E>("[12345678901234567890]", &options).unwrap();
+/// let number = Number::new("12345678901234567890").unwrap();
+/// assert_eq!(json, vec![JsonValue::Number(number)].into());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParserOptions {
+    pub(crate) json5: bool,
+    pub(crate) allow_comments: bool,
+    pub(crate) allow_trailing_commas: bool,
+    pub(crate) lossless_numbers: bool,
+    pub(crate) duplicate_keys: DuplicateKeys,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) max_input_len: Option<usize>,
+    pub(crate) max_string_len: Option<usize>,
+    pub(crate) max_array_len: Option<usize>,
+    pub(crate) max_object_len: Option<usize>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            json5: false,
+            allow_comments: false,
+            allow_trailing_commas: false,
+            lossless_numbers: false,
+            duplicate_keys: DuplicateKeys::default(),
+            max_depth: Some(Self::DEFAULT_MAX_DEPTH),
+            max_input_len: None,
+            max_string_len: None,
+            max_array_len: None,
+            max_object_len: None,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// The default maximum nesting depth of arrays and objects.
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a [`Parser`](struct.Parser.html) with these options.
+    pub fn build(self) -> Parser {
+        Parser::with_options(self)
+    }
+
+    /// Parses [JSON5](https://spec.json5.org) instead of JSON. Disabled by default.
+    ///
+    /// JSON5 adds comments, trailing commas, unquoted keys, single-quoted strings with more
+    /// escape sequences, and hexadecimal numbers, `Infinity`, `NaN` and numbers with a leading
+    /// `+` or a leading or trailing decimal point. Hexadecimal numbers, `Infinity` and `NaN` are
+    /// never kept as `JsonValue::Number` by `lossless_numbers`, and other numbers are kept in
+    /// their JSON form, such as `0.5` for `+.5`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use nom_json_parser::{json, ParserOptions};
+    ///
+    /// let parser = ParserOptions::new().json5(true).build();
+    /// let input = "{
+    ///     // comments are allowed
+    ///     unquoted: 'single quotes',
+    ///     hex: 0xFF,
+    ///     numbers: [+1, .5, 2., -Infinity,],
+    /// }";
+    /// let json = parser.parse_complete(input).unwrap();
+    /// assert_eq!(
+    ///     json,
+    ///     json!({
+    ///         "unquoted": "single quotes",
+    ///         "hex": 255,
+    ///         "numbers": [1, 0.5, 2.0, f64::NEG_INFINITY],
+    ///     })
+    /// );
+    /// ```
+    pub fn json5(mut self, enabled: bool) -> Self {
+        self.json5 = enabled;
+        self
+    }
+
+    /// Skips `//` and `/* */` comments wherever whitespace is allowed, as in the JSONC files of
+    /// VS Code such as `tsconfig.json`. Disabled by default, and always enabled in JSON5 mode.
+    ///
+    /// # Example
+    /// ```rust
+    /// use nom_json_parser::{json, ParserOptions};
+    ///
+    /// let parser = ParserOptions::new()
+    ///     .allow_comments(true)
+    ///     .allow_trailing_commas(true)
+    ///     .build();
+    /// let input = r#"{
+    ///     // Compile to ES2017.
+    ///     "target": "es2017",
+    ///     "lib": ["dom", /* "webworker", */ "es2017",],
+    /// }"#;
+    /// let json = parser.parse_complete(input).unwrap();
+    /// assert_eq!(json, json!({"target": "es2017", "lib": ["dom", "es2017"]}));
+    /// ```
+    pub fn allow_comments(mut self, enabled: bool) -> Self {
+        self.allow_comments = enabled;
+        self
+    }
+
+    /// Accepts a comma after the last element of an array or the last member of an object.
+    /// Disabled by default, and always enabled in JSON5 mode.
+    pub fn allow_trailing_commas(mut self, enabled: bool) -> Self {
+        self.allow_trailing_commas = enabled;
+        self
+    }
+
+    /// Keeps numbers as their source text in `JsonValue::Number`, instead of converting them to
+    /// `JsonValue::Int` or `JsonValue::Float`. Disabled by default.
+    pub fn lossless_numbers(mut self, enabled: bool) -> Self {
+        self.lossless_numbers = enabled;
+        self
+    }
+
+    /// Sets how objects with repeated keys are handled. Defaults to `DuplicateKeys::LastWins`.
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// Sets the maximum nesting depth of arrays and objects. Deeper input fails with
+    /// `JsonErrorKind::DepthLimitExceeded`. Defaults to `DEFAULT_MAX_DEPTH`.
+    ///
+    /// The parser is recursive, so `None` lets untrusted input overflow the stack.
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the maximum length of the input in bytes. Longer input fails with
+    /// `JsonErrorKind::InputTooLong` before any parsing is done. Unlimited by default.
+    pub fn max_input_len(mut self, max_len: Option<usize>) -> Self {
+        self.max_input_len = max_len;
+        self
+    }
+
+    /// Sets the maximum length in bytes of strings and object keys after unescaping. Longer
+    /// strings fail with `JsonErrorKind::StringTooLong`. Unlimited by default.
+    pub fn max_string_len(mut self, max_len: Option<usize>) -> Self {
+        self.max_string_len = max_len;
+        self
+    }
+
+    /// Sets the maximum number of elements in an array. The first element over the limit fails
+    /// with `JsonErrorKind::ArrayTooLong`, before the rest of the array is parsed. Unlimited by
+    /// default.
+    pub fn max_array_len(mut self, max_len: Option<usize>) -> Self {
+        self.max_array_len = max_len;
+        self
+    }
+
+    /// Sets the maximum number of members in an object, counting repeated keys. The first member
+    /// over the limit fails with `JsonErrorKind::ObjectTooLong`, before the rest of the object is
+    /// parsed. Unlimited by default.
+    pub fn max_object_len(mut self, max_len: Option<usize>) -> Self {
+        self.max_object_len = max_len;
+        self
+    }
+
+    /// Returns the whitespace skipped between tokens.
+    pub(crate) fn whitespace(&self) -> Whitespace {
+        if self.json5 {
+            Whitespace::Json5
+        } else if self.allow_comments {
+            Whitespace::Comments
+        } else {
+            Whitespace::Json
+        }
+    }
+
+    /// Returns whether a separator may follow the last element of an array or object.
+    pub(crate) fn trailing_commas(&self) -> bool {
+        self.json5 || self.allow_trailing_commas
+    }
+}
+
+/// The policy applied when an object contains the same key more than once.
+///
+/// RFC 8259 leaves the behavior unspecified, and parsers disagree on which value wins, which can
+/// be exploited when several parsers see the same document. `Error` rejects such documents.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{parse_with_options, DuplicateKeys, Json, ParserOptions};
+/// use maplit::btreemap;
+/// use nom::error::ErrorKind;
+/// type E<'a> = (&'a str, ErrorKind);
+///
+/// let input = r#"{"a": 1, "a": 2}"#;
+/// let options = ParserOptions::new().duplicate_keys(DuplicateKeys::Collect);
+/// let json: Json = btreemap! { "a" => vec![1, 2] }.into();
+/// assert_eq!(parse_with_options::<E>(input, &options), Ok(("", json)));
+///
+/// let options = ParserOptions::new().duplicate_keys(DuplicateKeys::Error);
+/// assert!(parse_with_options::<E>(input, &options).is_err());
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub enum DuplicateKeys {
+    /// Fails with `JsonErrorKind::DuplicateKey` at the second occurrence of the key.
+    Error,
+    /// Keeps the value of the first occurrence.
+    FirstWins,
+    /// Keeps the value of the last occurrence, at the position of the first one. This is the
+    /// default.
+    #[default]
+    LastWins,
+    /// Keeps the values of all occurrences in an array, in source order. Keys that occur only
+    /// once are unaffected.
+    Collect,
+}
@@ -1,9 +1,16 @@
-use crate::repr::Json;
+use crate::cst::Cst;
+use crate::error::{JsonError, JsonErrorKind, JsonParseError};
+use crate::map::Map;
+use crate::number::Number;
+use crate::options::{DuplicateKeys, ParserOptions};
+use crate::repr::{Json, JsonValue};
+use crate::spans::{with_spans, Spanned, Spans};
 use crate::utils::{
-    delimited_list, into, intoc, unescape, with_inputc, wrap_ws, ParserIteratorExt,
-    HIGH_SURROGATES, LOW_SURROGATES,
+    delimited_list, intoc, line_space0, space0, unescape, unescape_json5, with_inputc,
+    ParserIteratorExt, HIGH_SURROGATES, LOW_SURROGATES,
 };
 
+use indexmap::IndexMap;
 use nom::branch::alt;
 use nom::bytes::complete::*;
 use nom::bytes::streaming::escaped;
@@ -11,13 +18,14 @@ use nom::character::complete::*;
 use nom::combinator::mapc;
 use nom::combinator::*;
 use nom::error::{ErrorKind, ParseError};
-use nom::lib::std::collections::BTreeMap;
-use nom::number::complete::{double, recognize_float};
+use nom::multi::many0_count;
+use nom::sequence::precededc;
 use nom::sequence::*;
-use nom::sequence::{delimitedc, precededc};
-use nom::Err::Failure;
-use nom::{AsChar, IResult};
+use nom::Err::{Error, Failure, Incomplete};
+use nom::{AsChar, IResult, Offset};
 use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashSet;
 
 pub type ParserResult<'a, O, E> = IResult<&'a str, O, E>;
 pub type JsonResult<'a, E> = ParserResult<'a, Json<'a>, E>;
@@ -52,42 +60,558 @@ pub type JsonResult<'a, E> = ParserResult<'a, Json<'a>, E>;
 /// }.into();
 /// assert_eq!(result, Ok(("", json)));
 /// ```
-pub fn parse_json_element<'a, E: Clone + ParseError<&'a str>>(input: &'a str) -> JsonResult<'a, E> {
-    wrap_ws(parse_json)(input)
+pub fn parse_json_element<'a, E: JsonParseError<'a>>(input: &'a str) -> JsonResult<'a, E> {
+    Parser::default().parse_str(input)
 }
 
-fn parse_json<'a, E: Clone + ParseError<&'a str>>(input: &'a str) -> JsonResult<'a, E> {
+/// Parse a complete JSON document from string with the default options.
+///
+/// Unlike [`parse`](fn.parse.html), the whole input must be consumed: anything but whitespace after
+/// the value fails with `JsonErrorKind::TrailingCharacters`. Errors are located in `input`.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{parse_complete, Json, JsonErrorKind};
+///
+/// assert_eq!(parse_complete(" [1, 2]\n"), Ok(Json::from(vec![1, 2])));
+///
+/// let err = parse_complete("{\"a\": 1} garbage").unwrap_err();
+/// assert_eq!(err.kind(), JsonErrorKind::TrailingCharacters);
+/// assert_eq!(err.to_string().lines().next(), Some("trailing characters at line 1, column 10"));
+/// ```
+pub fn parse_complete(input: &str) -> Result<Json<'_>, JsonError<'_>> {
+    Parser::default().parse_complete(input)
+}
+
+/// Parse a complete JSON document from string into a lossless [`Cst`](struct.Cst.html) with the
+/// default options.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{json, parse_cst};
+///
+/// let input = "[\n  1,\n  2\n]\n";
+/// let mut cst = parse_cst(input).unwrap();
+/// assert_eq!(cst.to_string(), input);
+/// assert_eq!(cst.to_json(), json!([1, 2]));
+///
+/// cst.remove("/1").unwrap();
+/// assert_eq!(cst.to_string(), "[\n  1\n]\n");
+/// ```
+pub fn parse_cst(input: &str) -> Result<Cst<'_>, JsonError<'_>> {
+    Parser::default().parse_cst(input)
+}
+
+/// Parse a complete JSON document from string with the default options, along with the
+/// [`Spans`](struct.Spans.html) of its values and object keys.
+pub fn parse_with_spans(input: &str) -> Result<(Json<'_>, Spans), JsonError<'_>> {
+    Parser::default().parse_with_spans(input)
+}
+
+/// A JSON parser configured with [`ParserOptions`](struct.ParserOptions.html).
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{Json, ParserOptions};
+///
+/// let parser = ParserOptions::new().max_depth(Some(16)).build();
+///
+/// let (json, rest) = parser.parse_prefix("[1, 2] [3]").unwrap();
+/// assert_eq!(json, Json::from(vec![1, 2]));
+/// assert_eq!(rest, "[3]");
+///
+/// let err = parser.parse_complete("[1, 2] [3]").unwrap_err();
+/// assert_eq!(err.location().unwrap().column, 8);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Parser {
+    options: ParserOptions,
+}
+
+impl Parser {
+    /// Creates a parser with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a parser with the given options.
+    pub fn with_options(options: ParserOptions) -> Self {
+        Parser { options }
+    }
+
+    /// Returns the options of the parser.
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
+    }
+
+    /// Parses a JSON value with surrounding whitespace, returning the remaining input in nom's
+    /// `IResult`. The error type is chosen by the caller.
+    pub fn parse_str<'a, E: JsonParseError<'a>>(&self, input: &'a str) -> JsonResult<'a, E> {
+        parse_with_options(input, &self.options)
+    }
+
+    /// Parses a JSON value with surrounding whitespace from the start of `input`, and returns it
+    /// along with the remaining input.
+    pub fn parse_prefix<'a>(&self, input: &'a str) -> Result<(Json<'a>, &'a str), JsonError<'a>> {
+        match self.parse_str::<JsonError>(input) {
+            Ok((rest, json)) => Ok((json, rest)),
+            Err(err) => Err(locate(input, err)),
+        }
+    }
+
+    /// Parses a JSON value with surrounding whitespace, failing if anything else follows it.
+    pub fn parse_complete<'a>(&self, input: &'a str) -> Result<Json<'a>, JsonError<'a>> {
+        self.parse_document(input).map(|(json, _)| json)
+    }
+
+    /// Parses a complete JSON document into a lossless concrete syntax tree, which keeps the
+    /// whitespace and comments of the document. See [`Cst`](struct.Cst.html).
+    ///
+    /// All options apply, except that the tree keeps every member of an object with repeated
+    /// keys unless `duplicate_keys` is `DuplicateKeys::Error`. Its pointers and `to_json` resolve
+    /// them with `duplicate_keys`.
+    pub fn parse_cst<'a>(&self, input: &'a str) -> Result<Cst<'a>, JsonError<'a>> {
+        let (root, end) = self.parse_document(input)?;
+        Ok(Cst::new(root, end, &self.options))
+    }
+
+    /// Parses a complete document into `V`, along with the whitespace and comments at its end.
+    fn parse_document<'a, V: Syntax<'a>>(
+        &self,
+        input: &'a str,
+    ) -> Result<(V, &'a str), JsonError<'a>> {
+        match parse_document::<V, JsonError>(input, &self.options) {
+            Ok(("", output)) => Ok(output),
+            Ok((rest, _)) => Err(JsonError::from_json_error_kind(
+                rest,
+                JsonErrorKind::TrailingCharacters,
+            )
+            .locate(input)),
+            Err(err) => Err(locate(input, err)),
+        }
+    }
+
+    /// Parses a complete JSON document, along with the spans of its values and object keys. See
+    /// [`Spans`](struct.Spans.html).
+    pub fn parse_with_spans<'a>(&self, input: &'a str) -> Result<(Json<'a>, Spans), JsonError<'a>> {
+        let (value, _) = self.parse_document::<Spanned>(input)?;
+        Ok(with_spans(value, input))
+    }
+}
+
+/// Converts a parse error into a `JsonError` located in `input`.
+fn locate<'a>(input: &'a str, err: nom::Err<JsonError<'a>>) -> JsonError<'a> {
+    match err {
+        Error(err) | Failure(err) => err.locate(input),
+        Incomplete(_) => {
+            JsonError::from_expected(&input[input.len()..], "more input").locate(input)
+        }
+    }
+}
+
+impl From<ParserOptions> for Parser {
+    fn from(options: ParserOptions) -> Self {
+        Self::with_options(options)
+    }
+}
+
+/// Parse JSON from string with the given options
+///
+/// See [`ParserOptions`](struct.ParserOptions.html) for an example.
+pub fn parse_with_options<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+) -> JsonResult<'a, E> {
+    parse_document(input, options).map(|(input, (json, _))| (input, json))
+}
+
+/// Fails if `input` is longer than `max_input_len`.
+fn check_input_len<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+) -> Result<(), nom::Err<E>> {
+    match options.max_input_len {
+        Some(max_len) if input.len() > max_len => {
+            let end = (max_len..input.len())
+                .find(|&end| input.is_char_boundary(end))
+                .unwrap_or(max_len);
+            Err(Failure(E::from_json_error_kind(
+                &input[end..],
+                JsonErrorKind::InputTooLong,
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// A token of the source, with the whitespace and comments before it and after it up to the end of
+/// its line.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Lexeme<'a> {
+    pub(crate) leading: &'a str,
+    pub(crate) text: &'a str,
+    pub(crate) trailing: &'a str,
+}
+
+/// An object member as parsed, with the comma following it.
+pub(crate) struct Member<'a, V> {
+    pub(crate) key: Lexeme<'a>,
+    pub(crate) name: Cow<'a, str>,
+    pub(crate) colon: Lexeme<'a>,
+    pub(crate) value: V,
+    pub(crate) comma: Option<Lexeme<'a>>,
+}
+
+/// What the value parsers build from the tokens of a document: a `Json` value ignores the
+/// whitespace and comments around the tokens, while a [`CstValue`](struct.CstValue.html) keeps
+/// them.
+pub(crate) trait Syntax<'a>: Sized {
+    /// The elements of an array being parsed.
+    type Elements: Default;
+    /// The members of an object being parsed.
+    type Members: Default;
+
+    fn scalar(token: Lexeme<'a>, json: Json<'a>) -> Self;
+
+    fn push_element(elements: &mut Self::Elements, value: Self, comma: Option<Lexeme<'a>>);
+
+    fn array(open: Lexeme<'a>, elements: Self::Elements, close: Lexeme<'a>) -> Self;
+
+    /// Adds a member to an object, or returns `false` if `policy` rejects its key as repeated.
+    fn push_member(
+        members: &mut Self::Members,
+        member: Member<'a, Self>,
+        policy: DuplicateKeys,
+    ) -> bool;
+
+    fn object(open: Lexeme<'a>, members: Self::Members, close: Lexeme<'a>) -> Self;
+}
+
+/// A value that can stand for the values of a repeated key collected into an array.
+pub(crate) trait Collect {
+    /// Appends `value` to the collected values. Unless `collected`, `self` is the value of the
+    /// first occurrence, which becomes the first collected value.
+    fn collect(&mut self, value: Self, collected: bool);
+}
+
+/// The members of an object with repeated keys resolved by a `DuplicateKeys` policy.
+#[derive(Debug)]
+pub(crate) struct Members<'a, V> {
+    pub(crate) map: IndexMap<Cow<'a, str>, V>,
+    /// The keys whose values were collected into an array.
+    collected: HashSet<Cow<'a, str>>,
+}
+
+impl<V> Default for Members<'_, V> {
+    fn default() -> Self {
+        Members {
+            map: IndexMap::new(),
+            collected: HashSet::new(),
+        }
+    }
+}
+
+impl<'a, V: Collect> Members<'a, V> {
+    /// Adds a member, or returns `false` if its key is repeated and `policy` is
+    /// `DuplicateKeys::Error`.
+    pub(crate) fn insert(&mut self, key: Cow<'a, str>, value: V, policy: DuplicateKeys) -> bool {
+        let existing = match self.map.get_mut(&key) {
+            Some(existing) => existing,
+            None => {
+                self.map.insert(key, value);
+                return true;
+            }
+        };
+        match policy {
+            DuplicateKeys::Error => return false,
+            DuplicateKeys::FirstWins => {}
+            DuplicateKeys::LastWins => *existing = value,
+            DuplicateKeys::Collect => {
+                let collected = self.collected.contains(&key);
+                existing.collect(value, collected);
+                if !collected {
+                    self.collected.insert(key);
+                }
+            }
+        }
+        true
+    }
+}
+
+impl Collect for Json<'_> {
+    fn collect(&mut self, value: Self, collected: bool) {
+        match &mut self.0 {
+            Some(JsonValue::Array(values)) if collected => values.push(value),
+            _ => {
+                let first = Json(self.0.take());
+                *self = vec![first, value].into();
+            }
+        }
+    }
+}
+
+impl<'a> Syntax<'a> for Json<'a> {
+    type Elements = Vec<Json<'a>>;
+    type Members = Members<'a, Json<'a>>;
+
+    fn scalar(_: Lexeme<'a>, json: Json<'a>) -> Self {
+        json
+    }
+
+    fn push_element(elements: &mut Self::Elements, value: Self, _: Option<Lexeme<'a>>) {
+        elements.push(value);
+    }
+
+    fn array(_: Lexeme<'a>, elements: Self::Elements, _: Lexeme<'a>) -> Self {
+        elements.into()
+    }
+
+    fn push_member(
+        members: &mut Self::Members,
+        member: Member<'a, Self>,
+        policy: DuplicateKeys,
+    ) -> bool {
+        members.insert(member.name, member.value, policy)
+    }
+
+    fn object(_: Lexeme<'a>, members: Self::Members, _: Lexeme<'a>) -> Self {
+        Map(members.map).into()
+    }
+}
+
+/// Parses a value with the whitespace and comments around it, returning the ones after its last
+/// line separately.
+pub(crate) fn parse_document<'a, V: Syntax<'a>, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+) -> ParserResult<'a, (V, &'a str), E> {
+    check_input_len(input, options)?;
+    let (input, value) = parse_element(input, options, 0)?;
+    let (input, end) = space0(options.whitespace())(input)?;
+    Ok((input, (value, end)))
+}
+
+/// Parses a token with `parser`, along with the whitespace and comments around it.
+fn parse_token<'a, O, E: JsonParseError<'a>, F>(
+    input: &'a str,
+    options: &ParserOptions,
+    parser: F,
+) -> ParserResult<'a, (Lexeme<'a>, O), E>
+where
+    F: Fn(&'a str) -> ParserResult<'a, O, E>,
+{
+    let (input, leading) = space0(options.whitespace())(input)?;
+    token_after(input, leading, options, parser)
+}
+
+/// Parses a token with `parser` after the `leading` whitespace and comments, along with the ones
+/// after it up to the end of its line.
+fn token_after<'a, O, E: JsonParseError<'a>, F>(
+    input: &'a str,
+    leading: &'a str,
+    options: &ParserOptions,
+    parser: F,
+) -> ParserResult<'a, (Lexeme<'a>, O), E>
+where
+    F: Fn(&'a str) -> ParserResult<'a, O, E>,
+{
+    let (input, (text, output)) = with_inputc(input, parser)?;
+    let (input, trailing) = line_space0(options.whitespace())(input)?;
+    let token = Lexeme {
+        leading,
+        text,
+        trailing,
+    };
+    Ok((input, (token, output)))
+}
+
+/// Parses a JSON value with the whitespace and comments around its tokens, nested inside `depth`
+/// arrays or objects.
+fn parse_element<'a, V: Syntax<'a>, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+    depth: usize,
+) -> ParserResult<'a, V, E> {
+    let (input, leading) = space0(options.whitespace())(input)?;
+    match input.chars().next() {
+        Some('[') => parse_array(input, leading, options, depth),
+        Some('{') => parse_object(input, leading, options, depth),
+        _ => parse_scalar_token(input, leading, options),
+    }
+}
+
+fn parse_scalar_token<'a, V: Syntax<'a>, E: JsonParseError<'a>>(
+    input: &'a str,
+    leading: &'a str,
+    options: &ParserOptions,
+) -> ParserResult<'a, V, E> {
+    let scalar = |input| {
+        parse_scalar(input, options).map_err(|err| match err {
+            Error(_) => Error(E::from_expected(input, "a value")),
+            err => err,
+        })
+    };
+    let (input, (token, json)) = token_after(input, leading, options, scalar)?;
+    Ok((input, V::scalar(token, json)))
+}
+
+/// Parses `null`, a boolean, a number or a string.
+fn parse_scalar<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+) -> JsonResult<'a, E> {
     alt((
         parse_null,
         parse_true,
         parse_false,
-        parse_number,
-        parse_string,
-        parse_array,
-        parse_object,
+        |input| parse_number(input, options),
+        |input| parse_string(input, options),
     ))(input)
 }
 
-fn parse_null<'a, E: ParseError<&'a str>>(input: &'a str) -> JsonResult<'a, E> {
+pub(crate) fn parse_null<'a, E: ParseError<&'a str>>(input: &'a str) -> JsonResult<'a, E> {
     mapc(input, tag("null"), |_| Json::from(None))
 }
 
-fn parse_true<'a, E: ParseError<&'a str>>(input: &'a str) -> JsonResult<'a, E> {
+pub(crate) fn parse_true<'a, E: ParseError<&'a str>>(input: &'a str) -> JsonResult<'a, E> {
     mapc(input, tag("true"), |_| Json::from(true))
 }
 
-fn parse_false<'a, E: ParseError<&'a str>>(input: &'a str) -> JsonResult<'a, E> {
+pub(crate) fn parse_false<'a, E: ParseError<&'a str>>(input: &'a str) -> JsonResult<'a, E> {
     mapc(input, tag("false"), |_| Json::from(false))
 }
 
-fn parse_number<'a, E: ParseError<&'a str>>(input: &'a str) -> JsonResult<'a, E> {
-    let (input, (num_str, num)) = with_inputc(input, double)?;
-    let json = if num_str.contains(['.', 'e'].as_ref()) {
-        num.into()
+fn digits<'a, E: JsonParseError<'a>>(input: &'a str) -> ParserResult<'a, &'a str, E> {
+    digit1(input).map_err(|_: nom::Err<E>| Failure(E::from_expected(input, "a digit")))
+}
+
+pub(crate) fn parse_int_part<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+) -> ParserResult<'a, &'a str, E> {
+    match input.strip_prefix('0') {
+        Some(rest) if rest.starts_with(|c: char| c.is_ascii_digit()) => Err(Failure(
+            E::from_json_error_kind(rest, JsonErrorKind::LeadingZero),
+        )),
+        Some(rest) => Ok((rest, &input[..1])),
+        None => digits(input),
+    }
+}
+
+/// Recognizes a number following the grammar in RFC 8259:
+///
+/// ```text
+/// number = [ minus ] int [ frac ] [ exp ]
+/// ```
+///
+/// Returns the number and whether it is an integer, that is, it has neither a fraction nor an
+/// exponent.
+pub(crate) fn recognize_number<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+) -> ParserResult<'a, (&'a str, bool), E> {
+    peek(one_of("-0123456789"))(input)?;
+    let (input, (num_str, (_, _, frac, exp))) = with_inputc(
+        input,
+        tuple((
+            opt(char('-')),
+            parse_int_part,
+            opt(preceded(char('.'), digits)),
+            opt(tuple((one_of("eE"), opt(one_of("+-")), digits))),
+        )),
+    )?;
+    Ok((input, (num_str, frac.is_none() && exp.is_none())))
+}
+
+pub(crate) fn parse_number<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+) -> JsonResult<'a, E> {
+    if options.json5 {
+        return parse_json5_number(input, options);
+    }
+    let (input, (num_str, is_int)) = recognize_number(input)?;
+    Ok((input, number_value(num_str.into(), is_int, options)))
+}
+
+/// Converts a valid JSON number to a value, following `lossless_numbers`.
+fn number_value<'a>(num_str: Cow<'a, str>, is_int: bool, options: &ParserOptions) -> Json<'a> {
+    let float = || num_str.parse::<f64>().unwrap();
+    if options.lossless_numbers {
+        Number::from_lexeme(num_str).into()
+    } else if is_int {
+        num_str
+            .parse::<i64>()
+            .map(Into::into)
+            .or_else(|_| num_str.parse::<u64>().map(Into::into))
+            .unwrap_or_else(|_| float().into())
     } else {
-        num_str.parse::<i64>().map(Into::into).unwrap_or(num.into())
+        float().into()
+    }
+}
+
+/// Parses a JSON5 number. Besides JSON numbers, JSON5 has hexadecimal integers, `Infinity`,
+/// `NaN`, a leading `+`, and decimal points without digits on one side.
+fn parse_json5_number<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+) -> JsonResult<'a, E> {
+    let (rest, sign) = opt(one_of("+-"))(input)?;
+    let negative = sign == Some('-');
+    if let Some(rest) = rest.strip_prefix("Infinity") {
+        let inf = if negative {
+            f64::NEG_INFINITY
+        } else {
+            f64::INFINITY
+        };
+        return Ok((rest, inf.into()));
+    } else if let Some(rest) = rest.strip_prefix("NaN") {
+        return Ok((rest, f64::NAN.into()));
+    } else if let Some(hex) = rest.strip_prefix("0x").or_else(|| rest.strip_prefix("0X")) {
+        let (rest, hex) = hex_digit1(hex)
+            .map_err(|_: nom::Err<E>| Failure(E::from_expected(hex, "a hexadecimal digit")))?;
+        let json = match u64::from_str_radix(hex, 16) {
+            Ok(uint) if !negative => uint.into(),
+            Ok(uint) if uint <= i64::MIN.unsigned_abs() => (uint as i64).wrapping_neg().into(),
+            _ => {
+                let float = hex.chars().fold(0.0, |acc, digit| {
+                    acc * 16.0 + digit.to_digit(16).unwrap() as f64
+                });
+                (if negative { -float } else { float }).into()
+            }
+        };
+        return Ok((rest, json));
+    }
+
+    let (rest, int) = match rest.starts_with(|c: char| c.is_ascii_digit()) {
+        true => map(parse_int_part, Some)(rest)?,
+        false => (rest, None),
     };
-    Ok((input, json))
+    let (rest, frac) = opt(preceded(char('.'), digit0))(rest)?;
+    match (int, frac) {
+        (None, None) if sign.is_none() => {
+            return Err(Error(E::from_expected(input, "a number")));
+        }
+        (None, None) | (None, Some("")) => {
+            return Err(Failure(E::from_expected(rest, "a digit")));
+        }
+        _ => {}
+    }
+    let (rest, exp) = opt(recognize(tuple((one_of("eE"), opt(one_of("+-")), digits))))(rest)?;
+
+    // Rewrite the number in the JSON syntax when it uses JSON5 extensions.
+    let num_str = if sign == Some('+') || int.is_none() || frac == Some("") {
+        let frac = frac.map(|frac| if frac.is_empty() { "0" } else { frac });
+        Cow::Owned(format!(
+            "{}{}{}{}",
+            if negative { "-" } else { "" },
+            int.unwrap_or("0"),
+            frac.map_or(String::new(), |frac| format!(".{}", frac)),
+            exp.unwrap_or("")
+        ))
+    } else {
+        Cow::Borrowed(&input[..input.offset(rest)])
+    };
+    let is_int = frac.is_none() && exp.is_none();
+    Ok((rest, number_value(num_str, is_int, options)))
 }
 
 fn hex_u16<'a, E: ParseError<&'a str>>(input: &'a str) -> ParserResult<'a, u16, E> {
@@ -125,61 +649,375 @@ fn parse_escape_seq<'a, E: ParseError<&'a str>>(input: &'a str) -> ParserResult<
 }
 
 fn unquote<'a, E: ParseError<&'a str>>(input: &'a str) -> ParserResult<'a, &'a str, E> {
+    unquote_with(input, '"')
+}
+
+/// Matches a string delimited by `quote` and returns its escaped content. Inside single quotes,
+/// `\'` is accepted as an escape sequence as well.
+fn unquote_with<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+    quote: char,
+) -> ParserResult<'a, &'a str, E> {
+    let (unescaped, escape_seq) = match quote {
+        '\'' => (r"\'", "'"),
+        _ => (r#"\""#, ""),
+    };
+    precededc(
+        input,
+        char(quote),
+        cut(terminated(
+            escaped(
+                none_of(unescaped),
+                '\\',
+                alt((value((), one_of(escape_seq)), parse_escape_seq)),
+            ),
+            char(quote),
+        )),
+    )
+    .map_err(|err| match err {
+        // the streaming `escaped` asks for more input when the string is not terminated
+        Incomplete(_) => Failure(E::from_char(&input[input.len()..], quote)),
+        err => err,
+    })
+}
+
+/// Parses the escape sequence following a backslash in a JSON5 string.
+fn parse_json5_escape_seq<'a, E: ParseError<&'a str>>(input: &'a str) -> ParserResult<'a, (), E> {
+    alt((
+        parse_escape_seq,
+        value(
+            (),
+            alt((tag("\r\n"), recognize(one_of("\n\r\u{2028}\u{2029}")))),
+        ),
+        value(
+            (),
+            preceded(char('x'), take_while_m_n(2, 2, |c: char| c.is_hex_digit())),
+        ),
+        value((), terminated(char('0'), not(digit1))),
+        value((), none_of("0123456789xu")),
+    ))(input)
+}
+
+/// Matches a JSON5 string delimited by `quote` and returns its escaped content.
+fn unquote_json5<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+    quote: char,
+) -> ParserResult<'a, &'a str, E> {
+    let unescaped = match quote {
+        '\'' => "'\\\n\r",
+        _ => "\"\\\n\r",
+    };
     precededc(
         input,
-        char('"'),
+        char(quote),
         cut(terminated(
-            escaped(none_of(r#"\""#), '\\', parse_escape_seq),
-            char('"'),
+            escaped(none_of(unescaped), '\\', parse_json5_escape_seq),
+            char(quote),
+        )),
+    )
+    .map_err(|err| match err {
+        Incomplete(_) => Failure(E::from_char(&input[input.len()..], quote)),
+        err => err,
+    })
+}
+
+/// Parses a JSON5 string, delimited by double or single quotes, and unescapes it.
+fn parse_json5_string_raw<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+) -> ParserResult<'a, Cow<'a, str>, E> {
+    mapc(
+        input,
+        alt((
+            |input| unquote_json5(input, '"'),
+            |input| unquote_json5(input, '\''),
         )),
+        unescape_json5,
     )
 }
 
-fn parse_string_raw<'a, E: ParseError<&'a str>>(
+/// Parses a JSON5 identifier used as an object key, which may contain `\uXXXX` escape sequences.
+fn parse_identifier<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+) -> ParserResult<'a, Cow<'a, str>, E> {
+    let escape = |input| {
+        recognize(preceded(
+            tag("\\u"),
+            take_while_m_n(4, 4, |c: char| c.is_hex_digit()),
+        ))(input)
+    };
+    let start = |c: &char| c.is_alphabetic() || matches!(c, '$' | '_');
+    let part = |c: &char| start(c) || c.is_numeric() || matches!(c, '\u{200c}' | '\u{200d}');
+    mapc(
+        input,
+        recognize(pair(
+            alt((recognize(verify(anychar, start)), escape)),
+            many0_count(alt((recognize(verify(anychar, part)), escape))),
+        )),
+        unescape_json5,
+    )
+}
+
+/// Parses a string delimited by `quote` and unescapes it.
+pub(crate) fn parse_quoted<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+    quote: char,
+) -> ParserResult<'a, Cow<'a, str>, E> {
+    mapc(input, |input| unquote_with(input, quote), unescape)
+}
+
+pub(crate) fn parse_string_raw<'a, E: ParseError<&'a str>>(
     input: &'a str,
 ) -> ParserResult<'a, Cow<'a, str>, E> {
     mapc(input, unquote, unescape)
 }
 
-fn parse_string<'a, E: ParseError<&'a str>>(input: &'a str) -> JsonResult<'a, E> {
-    intoc(input, parse_string_raw)
+/// Parses a string with `parse_string_raw`, or `parse_json5_string_raw` in JSON5 mode, failing if
+/// it is longer than `max_string_len` after unescaping.
+fn parse_string_limited<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+) -> ParserResult<'a, Cow<'a, str>, E> {
+    let parsed = if options.json5 {
+        parse_json5_string_raw(input)
+    } else {
+        parse_string_raw(input)
+    };
+    limit_string_len(input, parsed?, options)
+}
+
+/// Parses an object key: a string, or in JSON5 mode an identifier as well.
+fn parse_key<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+) -> ParserResult<'a, Cow<'a, str>, E> {
+    if options.json5 {
+        match parse_string_limited(input, options) {
+            Err(Error(_)) => limit_string_len(input, parse_identifier(input)?, options),
+            res => res,
+        }
+    } else {
+        parse_string_limited(input, options)
+    }
+}
+
+/// Fails if the string parsed from `input` is longer than `max_string_len`.
+fn limit_string_len<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+    (rest, string): (&'a str, Cow<'a, str>),
+    options: &ParserOptions,
+) -> ParserResult<'a, Cow<'a, str>, E> {
+    match options.max_string_len {
+        Some(max_len) if string.len() > max_len => Err(Failure(E::from_json_error_kind(
+            input,
+            JsonErrorKind::StringTooLong,
+        ))),
+        _ => Ok((rest, string)),
+    }
+}
+
+fn parse_string<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+) -> JsonResult<'a, E> {
+    intoc(input, |input| parse_string_limited(input, options))
 }
 
-fn parse_array<'a, E: Clone + ParseError<&'a str>>(input: &'a str) -> JsonResult<'a, E> {
-    delimitedc(
+/// Matches the delimiter closing an array or object. On failure, the error lists the tokens that
+/// could have appeared instead: `first` when the list is still empty, or a separator otherwise.
+fn closing<'a, E: JsonParseError<'a>>(
+    delimiter: char,
+    first: Option<&'static str>,
+) -> impl Fn(&'a str) -> ParserResult<'a, char, E> {
+    move |input| {
+        char(delimiter)(input).map_err(|_: nom::Err<E>| {
+            let alternative = match first {
+                Some(first) => E::from_expected(input, first),
+                None => E::from_char(input, ','),
+            };
+            Failure(alternative.or(E::from_char(input, delimiter)))
+        })
+    }
+}
+
+/// Returns the depth of the values inside an array or object starting at `input`, or fails if the
+/// array or object is nested too deeply.
+fn nested<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+    depth: usize,
+) -> Result<usize, nom::Err<E>> {
+    match options.max_depth {
+        Some(max_depth) if depth >= max_depth => Err(Failure(E::from_json_error_kind(
+            input,
+            JsonErrorKind::DepthLimitExceeded,
+        ))),
+        _ => Ok(depth + 1),
+    }
+}
+
+/// Fails with `kind` at the next item of a list once it holds `max_len` items, unless the list
+/// is closed by `close` instead.
+fn check_len<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+    len: usize,
+    max_len: Option<usize>,
+    close: char,
+    kind: JsonErrorKind,
+) -> ParserResult<'a, (), E> {
+    match max_len {
+        Some(max_len) if len >= max_len => {
+            let (input, _) = space0(options.whitespace())(input)?;
+            if input.starts_with(close) {
+                Ok((input, ()))
+            } else {
+                Err(Failure(E::from_json_error_kind(input, kind)))
+            }
+        }
+        _ => Ok((input, ())),
+    }
+}
+
+/// Returns a parser of the `close` delimiter of a list, which may follow a trailing comma if the
+/// options allow one.
+fn list_end<'a, E: JsonParseError<'a>>(
+    options: &ParserOptions,
+    close: char,
+) -> Option<impl Fn(&'a str) -> ParserResult<'a, char, E>> {
+    let ws = options.whitespace();
+    options
+        .trailing_commas()
+        .then(|| preceded(space0(ws), char(close)))
+}
+
+/// Parses the comma separating the elements of an array or the members of an object.
+fn parse_comma<'a, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+) -> ParserResult<'a, Lexeme<'a>, E> {
+    map(
+        |input| parse_token(input, options, char(',')),
+        |(comma, _)| comma,
+    )(input)
+}
+
+fn parse_array<'a, V: Syntax<'a>, E: JsonParseError<'a>>(
+    input: &'a str,
+    leading: &'a str,
+    options: &ParserOptions,
+    depth: usize,
+) -> ParserResult<'a, V, E> {
+    let depth = nested(input, options, depth)?;
+    let (input, (open, _)) = token_after(input, leading, options, char('['))?;
+    let index = Cell::new(0);
+    let (input, elements) = delimited_list(
         input,
-        terminated(char('['), multispace0),
         |input| {
-            delimited_list(input, parse_json_element, char(','))
-                .process(|it| it.collect::<Vec<_>>().into())
+            let i = index.replace(index.get() + 1);
+            let max_len = options.max_array_len;
+            check_len(input, options, i, max_len, ']', JsonErrorKind::ArrayTooLong)?;
+            parse_element(input, options, depth).map_err(|err| err.map(|err: E| err.in_index(i)))
         },
-        preceded(multispace0, char(']')),
+        |input| parse_comma(input, options),
+        list_end(options, ']'),
     )
+    .process(|it| {
+        let mut elements = V::Elements::default();
+        let mut len = 0;
+        for (value, comma) in it {
+            V::push_element(&mut elements, value, comma);
+            len += 1;
+        }
+        (elements, len)
+    })?;
+    let (elements, len) = elements;
+    let first = if len == 0 { Some("a value") } else { None };
+    let (input, (close, _)) = parse_token(input, options, closing(']', first))?;
+    Ok((input, V::array(open, elements, close)))
 }
 
-fn parse_object<'a, E: Clone + ParseError<&'a str>>(input: &'a str) -> JsonResult<'a, E> {
-    delimitedc(
+/// Parses an object member, returning the input at the start of the key along with the member.
+fn parse_member<'a, V: Syntax<'a>, E: JsonParseError<'a>>(
+    input: &'a str,
+    options: &ParserOptions,
+    depth: usize,
+) -> ParserResult<'a, (&'a str, Member<'a, V>), E> {
+    let (key_input, leading) = space0(options.whitespace())(input)?;
+    let (input, (key, name)) = token_after(key_input, leading, options, |input| {
+        parse_key(input, options)
+    })?;
+    let in_key = |err: nom::Err<E>| match err {
+        Error(err) | Failure(err) => Failure(err.in_key(name.clone())),
+        err => err,
+    };
+    let (input, (colon, _)) = parse_token(input, options, char(':')).map_err(in_key)?;
+    let (input, value) = parse_element(input, options, depth).map_err(in_key)?;
+    let member = Member {
+        key,
+        name,
+        colon,
+        value,
+        comma: None,
+    };
+    Ok((input, (key_input, member)))
+}
+
+fn parse_object<'a, V: Syntax<'a>, E: JsonParseError<'a>>(
+    input: &'a str,
+    leading: &'a str,
+    options: &ParserOptions,
+    depth: usize,
+) -> ParserResult<'a, V, E> {
+    let depth = nested(input, options, depth)?;
+    let (input, (open, _)) = token_after(input, leading, options, char('{'))?;
+    let index = Cell::new(0);
+    let (input, members) = delimited_list(
         input,
-        terminated(char('{'), multispace0),
         |input| {
-            delimited_list(
+            let i = index.replace(index.get() + 1);
+            let max_len = options.max_object_len;
+            check_len(
                 input,
-                tuple((
-                    wrap_ws(parse_string_raw),
-                    preceded(char(':'), parse_json_element),
-                )),
-                char(','),
-            )
-            .process(|it| it.collect::<BTreeMap<_, _>>().into())
+                options,
+                i,
+                max_len,
+                '}',
+                JsonErrorKind::ObjectTooLong,
+            )?;
+            parse_member(input, options, depth)
         },
-        terminated(multispace0, char('}')),
+        |input| parse_comma(input, options),
+        list_end(options, '}'),
     )
+    .process(|it| {
+        let mut members = V::Members::default();
+        let mut len = 0;
+        for ((key_input, mut member), comma) in it {
+            member.comma = comma;
+            if !V::push_member(&mut members, member, options.duplicate_keys) {
+                return Err(Failure(E::from_json_error_kind(
+                    key_input,
+                    JsonErrorKind::DuplicateKey,
+                )));
+            }
+            len += 1;
+        }
+        Ok((members, len))
+    })?;
+    let (members, len) = members?;
+    let first = match (len == 0, options.json5) {
+        (true, false) => Some("a string"),
+        (true, true) => Some("a key"),
+        (false, _) => None,
+    };
+    let (input, (close, _)) = parse_token(input, options, closing('}', first))?;
+    Ok((input, V::object(open, members, close)))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::repr::JsonValue;
+    use crate::error::JsonContext;
     use crate::utils::escape;
     use assert_matches::assert_matches;
     use itertools::Itertools;
@@ -190,6 +1028,14 @@ mod test {
 
     type E<'a> = (&'a str, ErrorKind);
 
+    fn string(input: &str) -> JsonResult<'_, E<'_>> {
+        parse_string(input, &ParserOptions::default())
+    }
+
+    fn number(input: &str) -> JsonResult<'_, E<'_>> {
+        parse_number(input, &ParserOptions::default())
+    }
+
     fn arb_json_number() -> num::f64::Any {
         num::f64::POSITIVE | num::f64::NEGATIVE | num::f64::ZERO
     }
@@ -203,6 +1049,7 @@ mod test {
             Just(None.into()),
             any::<bool>().prop_map(Into::into),
             any::<i64>().prop_map(Into::into),
+            any::<u64>().prop_map(Into::into),
             arb_json_number().prop_map(Into::into),
             arb_json_string().prop_map(|s| s.into())
         ];
@@ -214,6 +1061,45 @@ mod test {
         })
     }
 
+    fn nest(open: &str, close: &str, depth: usize) -> String {
+        format!("{}null{}", open.repeat(depth), close.repeat(depth))
+    }
+
+    #[test]
+    fn test_parse_pathological_nesting() {
+        let depth = 100_000;
+        let max_depth = ParserOptions::DEFAULT_MAX_DEPTH;
+        let input = nest("[", "]", depth);
+        assert_eq!(
+            parse_json_element::<E>(&input),
+            Err(Failure((&input[max_depth..], ErrorKind::TooLarge)))
+        );
+        let input = nest(r#"{"a":"#, "}", depth);
+        assert_eq!(
+            parse_json_element::<E>(&input),
+            Err(Failure((&input[5 * max_depth..], ErrorKind::TooLarge)))
+        );
+    }
+
+    #[test]
+    fn test_parse_max_depth() {
+        let max_depth = ParserOptions::DEFAULT_MAX_DEPTH;
+        let input = nest("[{\"a\": ", "}]", max_depth / 2);
+        assert_matches!(parse_json_element::<E>(&input), Ok(("", _)));
+        let input = nest("[", "]", max_depth + 1);
+        assert_matches!(parse_json_element::<E>(&input), Err(Failure(_)));
+
+        let options = ParserOptions::new().max_depth(Some(2));
+        assert_matches!(parse_with_options::<E>("[[1], {}]", &options), Ok(("", _)));
+        assert_eq!(
+            parse_with_options::<E>("[[1], {\"a\": []}]", &options),
+            Err(Failure(("[]}]", ErrorKind::TooLarge)))
+        );
+        let options = ParserOptions::new().max_depth(None);
+        let input = nest("[", "]", max_depth + 1);
+        assert_matches!(parse_with_options::<E>(&input, &options), Ok(("", _)));
+    }
+
     #[test]
     fn test_parse_escape_seq() {
         assert_eq!(parse_escape_seq::<E>("t rest"), Ok((" rest", ())));
@@ -280,51 +1166,301 @@ mod test {
     #[test]
     fn test_parse_string() {
         assert_eq!(
-            parse_string::<E>(r#""a normal string" rest"#),
+            string(r#""a normal string" rest"#),
             Ok((" rest", "a normal string".into()))
         );
         assert_eq!(
-            parse_string::<E>(r#""a\r\nstring\r\nwith\r\nnewlines\r\non\r\nwindows" rest"#),
+            string(r#""a\r\nstring\r\nwith\r\nnewlines\r\non\r\nwindows" rest"#),
             Ok((
                 " rest",
                 "a\r\nstring\r\nwith\r\nnewlines\r\non\r\nwindows".into()
             ))
         );
         assert_eq!(
-            parse_string::<E>(r#""\u4e00\u4e2a\u542b\u6709UTF-16\u7684\u5b57\u7b26\u4e32" rest"#),
+            string(r#""\u4e00\u4e2a\u542b\u6709UTF-16\u7684\u5b57\u7b26\u4e32" rest"#),
             // "一个含有UTF-16的字符串"
             Ok((" rest", "一个含有UTF-16的字符串".into()))
         );
         assert_eq!(
-            parse_string::<E>(r#""\uD834\uDD1E\u006d\u0075\u0073\uDD1E\u0069\u0063\uD834""#),
+            string(r#""\uD834\uDD1E\u006d\u0075\u0073\uDD1E\u0069\u0063\uD834""#),
             Err(Failure(E::from_error_kind("DD1E", ErrorKind::HexDigit)))
         );
         assert_eq!(
-            parse_string::<E>(r#""\uD834\uE000\u006d\u0075\u0073\uDD1E\u0069\u0063\uD834""#),
+            string(r#""\uD834\uE000\u006d\u0075\u0073\uDD1E\u0069\u0063\uD834""#),
             Err(Failure(E::from_error_kind("E000", ErrorKind::HexDigit)))
         );
     }
 
     #[test]
     fn test_parse_string_empty() {
-        assert_eq!(parse_string::<E>(r#""""#), Ok(("", "".into())));
+        assert_eq!(string(r#""""#), Ok(("", "".into())));
     }
 
     #[test]
     fn test_parse_string_invalid_escape() {
-        assert_matches!(parse_string::<E>(r#"hello\a\world"#), Err(_));
-        assert_matches!(parse_string::<E>(r#"utf16: \uff"#), Err(_));
+        assert_matches!(string(r#"hello\a\world"#), Err(_));
+        assert_matches!(string(r#"utf16: \uff"#), Err(_));
+    }
+
+    #[test]
+    fn test_parse_number_strict() {
+        assert_eq!(number("0"), Ok(("", 0.into())));
+        assert_eq!(number("-0"), Ok(("", 0.into())));
+        assert_eq!(number("-12 rest"), Ok((" rest", (-12).into())));
+        assert_eq!(number("18446744073709551615"), Ok(("", u64::MAX.into())));
+        assert_eq!(
+            number("18446744073709551616"),
+            Ok(("", 18446744073709551616f64.into()))
+        );
+        assert_eq!(
+            number("-9223372036854775809"),
+            Ok(("", (-9223372036854775809f64).into()))
+        );
+        assert_eq!(number("0.5"), Ok(("", 0.5.into())));
+        assert_eq!(number("1e5"), Ok(("", 1e5.into())));
+        assert_eq!(number("1E5"), Ok(("", 1e5.into())));
+        assert_eq!(number("1E+5"), Ok(("", 1e5.into())));
+        assert_eq!(number("-2.5e-3"), Ok(("", (-2.5e-3).into())));
+
+        assert_matches!(number("+1"), Err(nom::Err::Error(_)));
+        assert_matches!(number(".5"), Err(nom::Err::Error(_)));
+        assert_matches!(number("inf"), Err(nom::Err::Error(_)));
+        assert_matches!(number("nan"), Err(nom::Err::Error(_)));
+        assert_matches!(number("-inf"), Err(Failure(("inf", _))));
+        assert_matches!(number("1."), Err(Failure(("", _))));
+        assert_matches!(number("1.e5"), Err(Failure(("e5", _))));
+        assert_matches!(number("1e+"), Err(Failure(("", _))));
+        assert_eq!(number("007"), Err(Failure(("07", ErrorKind::Digit))));
+        assert_eq!(number("-01.5"), Err(Failure(("1.5", ErrorKind::Digit))));
+    }
+
+    #[test]
+    fn test_parse_lossless_numbers() {
+        let options = ParserOptions::new().lossless_numbers(true);
+        let input = "[18446744073709551615, 0.1000000000000000055, -1E-7, 3]";
+        let (rest, json) = parse_with_options::<E>(input, &options).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(json.to_string(), input);
+        assert_matches!(json[0].as_value(), Some(JsonValue::Number(_)));
+        assert_eq!(json[0].as_u64(), Some(u64::MAX));
+        assert_eq!(json[3].as_i64(), Some(3));
+    }
+
+    #[test]
+    fn test_parse_object_order() {
+        let input = r#"{"zeta": 1, "alpha": {"y": [], "x": {}}, "mid": null}"#;
+        let (_, json) = parse_json_element::<E>(input).unwrap();
+        assert_eq!(json.to_string(), input);
+    }
+
+    #[test]
+    fn test_parse_duplicate_keys() {
+        let input = r#"{"a": 1, "b": [0], "a": 2, "b": [], "a": 3}"#;
+        let parse = |policy| {
+            let options = ParserOptions::new().duplicate_keys(policy);
+            parse_with_options::<E>(input, &options).map(|(_, json)| json.to_string())
+        };
+        assert_eq!(
+            parse(DuplicateKeys::default()),
+            Ok(r#"{"a": 3, "b": []}"#.to_string())
+        );
+        assert_eq!(
+            parse(DuplicateKeys::FirstWins),
+            Ok(r#"{"a": 1, "b": [0]}"#.to_string())
+        );
+        assert_eq!(
+            parse(DuplicateKeys::Collect),
+            Ok(r#"{"a": [1, 2, 3], "b": [[0], []]}"#.to_string())
+        );
+        assert_eq!(
+            parse(DuplicateKeys::Error),
+            Err(Failure((&input[19..], ErrorKind::Verify)))
+        );
+    }
+
+    #[test]
+    fn test_parse_duplicate_keys_nested() {
+        let input = "[{\"a\": 1}, {\"a\": 1, \"a\": 2}]";
+        let options = ParserOptions::new().duplicate_keys(DuplicateKeys::Error);
+        let err = match parse_with_options::<JsonError>(input, &options) {
+            Err(Failure(err)) => err.locate(input),
+            res => panic!("unexpected result: {:?}", res),
+        };
+        assert_eq!(err.kind(), JsonErrorKind::DuplicateKey);
+        assert_eq!(err.location().map(|loc| loc.offset), Some(20));
+        assert_eq!(err.context(), &[JsonContext::Index(1)]);
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        let limited = |input, options: &ParserOptions| match parse_with_options::<JsonError>(
+            input, options,
+        ) {
+            Err(Failure(err)) => (err.kind(), input.len() - err.input().len()),
+            res => panic!("unexpected result: {:?}", res),
+        };
+        let input = r#"{"key": ["a", "bcd", "\u00e9"], "long key": 1}"#;
+        let options = ParserOptions::new()
+            .max_input_len(Some(input.len()))
+            .max_string_len(Some(8))
+            .max_array_len(Some(3))
+            .max_object_len(Some(2));
+        assert_matches!(parse_with_options::<E>(input, &options), Ok(("", _)));
+
+        let options = ParserOptions::new().max_input_len(Some(10));
+        assert_eq!(limited(input, &options), (JsonErrorKind::InputTooLong, 10));
+        assert_eq!(limited("\"\u{e9}\"", &options.max_input_len(Some(2))).1, 3);
+        let options = ParserOptions::new().max_string_len(Some(7));
+        assert_eq!(limited(input, &options), (JsonErrorKind::StringTooLong, 32));
+        let options = ParserOptions::new().max_string_len(Some(2));
+        assert_eq!(limited(input, &options), (JsonErrorKind::StringTooLong, 1));
+        let options = ParserOptions::new().max_array_len(Some(2));
+        assert_eq!(limited(input, &options), (JsonErrorKind::ArrayTooLong, 21));
+        let options = ParserOptions::new().max_object_len(Some(1));
+        assert_eq!(limited(input, &options), (JsonErrorKind::ObjectTooLong, 32));
+        assert_matches!(
+            parse_with_options::<E>("[]", &options.max_array_len(Some(0))),
+            Ok(_)
+        );
+
+        let options = ParserOptions::new().max_array_len(Some(1));
+        assert_eq!(
+            limited("[1, tru]", &options),
+            (JsonErrorKind::ArrayTooLong, 4)
+        );
+        assert_eq!(
+            limited("[1, [[[", &options),
+            (JsonErrorKind::ArrayTooLong, 4)
+        );
+        let options = ParserOptions::new().max_object_len(Some(1));
+        let input = r#"{"a": 1, "b" 2}"#;
+        assert_eq!(limited(input, &options), (JsonErrorKind::ObjectTooLong, 9));
+        let options = options.allow_trailing_commas(true);
+        assert_matches!(parse_with_options::<E>(r#"{"a": 1, }"#, &options), Ok(_));
+    }
+
+    #[test]
+    fn test_parser() {
+        let parser = Parser::new();
+        assert_eq!(parser.parse_prefix(" 1 , 2"), Ok((1.into(), ", 2")));
+        assert_eq!(parser.parse_complete(" [true] \n"), Ok(vec![true].into()));
+        assert_eq!(
+            parser.parse_str::<E>("null rest"),
+            Ok(("rest", None.into()))
+        );
+
+        let err = parser.parse_complete("[true]\n false").unwrap_err();
+        assert_eq!(err.kind(), JsonErrorKind::TrailingCharacters);
+        assert_eq!(err.input(), "false");
+        assert_eq!(
+            err.location().map(|loc| (loc.line, loc.column)),
+            Some((2, 2))
+        );
+
+        let err = parser.parse_prefix("[tru]").unwrap_err();
+        assert_eq!(err.location().map(|loc| loc.offset), Some(1));
+
+        let parser = ParserOptions::new().max_array_len(Some(1)).build();
+        assert_eq!(parser.options().max_array_len, Some(1));
+        let err = parser.parse_complete("[1, 2]").unwrap_err();
+        assert_eq!(err.kind(), JsonErrorKind::ArrayTooLong);
+    }
+
+    #[test]
+    fn test_parse_list_separators() {
+        assert_eq!(
+            parse_json_element::<E>("[ ]"),
+            Ok(("", Vec::<Json>::new().into()))
+        );
+        assert_matches!(parse_json_element::<E>("[1,]"), Err(Failure(_)));
+        assert_matches!(parse_json_element::<E>("[1 2]"), Err(Failure(_)));
+        assert_matches!(parse_json_element::<E>(r#"{"a": 1,}"#), Err(Failure(_)));
+        assert_matches!(parse_json_element::<E>(r#"{"a" 1}"#), Err(Failure(_)));
+    }
+
+    #[test]
+    fn test_parse_comments_and_trailing_commas() {
+        let options = ParserOptions::new().allow_trailing_commas(true);
+        assert_eq!(
+            parse_with_options::<E>(r#"[1, {"a": [], }, ]"#, &options),
+            Ok(("", json!([1, {"a": []}])))
+        );
+        assert_matches!(parse_with_options::<E>("[,]", &options), Err(Failure(_)));
+        assert_matches!(parse_with_options::<E>("[1,,]", &options), Err(Failure(_)));
+        assert_matches!(parse_with_options::<E>("{,}", &options), Err(Failure(_)));
+        assert_matches!(
+            parse_with_options::<E>("[1] // a", &options),
+            Ok(("// a", _))
+        );
+
+        let options = ParserOptions::new().allow_comments(true);
+        let input = "/* a */ [1 /* b * / */, // c\n 2 //]\n] // d";
+        assert_eq!(
+            parse_with_options::<E>(input, &options),
+            Ok(("", vec![1, 2].into()))
+        );
+        assert_matches!(
+            parse_with_options::<E>("[1, 2,]", &options),
+            Err(Failure(_))
+        );
+        assert_matches!(
+            parse_with_options::<E>("[1 / 2]", &options),
+            Err(Failure(_))
+        );
+        assert_eq!(
+            parse_with_options::<E>("[1 /* b", &options),
+            Err(Failure(("", ErrorKind::Tag)))
+        );
+        assert_eq!(
+            parse_with_options::<E>("\u{a0}[]", &options),
+            Err(Error(("\u{a0}[]", ErrorKind::Tag)))
+        );
+    }
+
+    #[test]
+    fn test_parse_trailing_comma_errors() {
+        let jsonc = ParserOptions::new()
+            .allow_comments(true)
+            .allow_trailing_commas(true);
+        for options in [jsonc, ParserOptions::new().json5(true)] {
+            let parser = options.build();
+            let message = |input| {
+                let err = parser.parse_complete(input).unwrap_err().to_string();
+                err.lines().next().unwrap().to_string()
+            };
+            assert_eq!(
+                message("[1, tru]"),
+                "expected a value inside array index 1 at line 1, column 5"
+            );
+            assert_eq!(
+                message("[1,,]"),
+                "expected a value inside array index 1 at line 1, column 4"
+            );
+            assert_eq!(
+                message(r#"{"a": 1, "b" 2}"#),
+                "expected `:` inside key \"b\" at line 1, column 14"
+            );
+            assert_eq!(
+                message(r#"{"a": 1, "b": }"#),
+                "expected a value inside key \"b\" at line 1, column 15"
+            );
+            assert_eq!(
+                message("[1, // c\n 2"),
+                "expected `,` or `]` at line 2, column 3"
+            );
+            assert_eq!(parser.parse_complete("[1, // c\n]"), Ok(vec![1].into()));
+        }
     }
 
     proptest! {
         #[test]
         fn test_parse_string_random(s in "\\PC*") {
-            let _ = parse_string::<E>(&s);
+            let _ = string(&s);
         }
 
         #[test]
         fn test_parse_string_no_escape(s in r#""[^\pC\\"]*""#) {
-            let res = parse_string::<E>(&s);
+            let res = string(&s);
             prop_assert_eq!(res, Ok(("", Json::from(&s[1..s.len() - 1]))));
         }
 
@@ -332,7 +1468,7 @@ mod test {
         fn test_parse_string_regular_escape(s in r#""(?:[^\pC\\"/]|\\[\\/"bfnrt])*"[^"]*"#) {
             let split = &s.rfind('"').unwrap() + 1;
             let unquoted = &s[1..split - 1];
-            if let Ok((rest, Json(Some(JsonValue::String(res))))) = parse_string::<E>(&s) {
+            if let Ok((rest, Json(Some(JsonValue::String(res))))) = string(&s) {
                 prop_assert_eq!(rest, &s[split..]);
                 prop_assert_eq!(&escape(&res).replace('/', "\\/"), unquoted);
             } else {
@@ -343,7 +1479,7 @@ mod test {
         #[test]
         fn test_parse_string_utf16(orig in r#"[^\pC\\"]*"#, rest in r#"[^"]*"#) {
             let s = format!(r#""{}"{}"#, orig.encode_utf16().format_with("", |cp, f| f(&format_args!("\\u{:04X}", cp))), rest);
-            if let Ok((remaining, Json(Some(JsonValue::String(res))))) = parse_string::<E>(&s) {
+            if let Ok((remaining, Json(Some(JsonValue::String(res))))) = string(&s) {
                 prop_assert_eq!(remaining, rest);
                 prop_assert_eq!(res, orig);
             } else {
@@ -353,7 +1489,7 @@ mod test {
 
         #[test]
         fn test_parse_int(i: i64, rest in r#" \PC*"#) {
-            if let Ok((remaining, Json(Some(JsonValue::Int(res))))) = parse_number::<E>(&format!("{}{}", i, rest)) {
+            if let Ok((remaining, Json(Some(JsonValue::Int(res))))) = number(&format!("{}{}", i, rest)) {
                 prop_assert_eq!(remaining, rest);
                 prop_assert_eq!(res, i);
             } else {
@@ -361,9 +1497,15 @@ mod test {
             }
         }
 
+        #[test]
+        fn test_parse_uint(u in (i64::MAX as u64 + 1).., rest in r#" \PC*"#) {
+            let input = format!("{}{}", u, rest);
+            prop_assert_eq!(number(&input), Ok((rest.as_str(), Json(Some(JsonValue::UInt(u))))));
+        }
+
         #[test]
         fn test_parse_float(f in arb_json_number(), rest in r#" \PC*"#) {
-            match parse_number::<E>(&format!("{}{}", f, rest)) {
+            match number(&format!("{}{}", f, rest)) {
                 Ok((remaining, Json(Some(JsonValue::Int(res))))) => {
                     prop_assert_eq!(remaining, rest);
                     prop_assert_eq!(res as f64, f);
@@ -372,7 +1514,7 @@ mod test {
                     prop_assert_eq!(remaining, rest);
                     prop_assert_eq!(res, f);
                 },
-                e @ _ => {
+                e => {
                     eprintln!("{:?}", e);
                     prop_assert!(false);
                 }
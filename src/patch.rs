@@ -0,0 +1,495 @@
+use crate::map::Map;
+use crate::pointer::{JsonPointer, PointerError};
+use crate::repr::{equal, Json, JsonValue};
+
+use std::fmt::{Display, Error, Formatter};
+
+/// A JSON Patch document as defined in RFC 6902: a sequence of operations applied in order.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{parse_complete, Patch};
+///
+/// let mut json = parse_complete(r#"{"name": "Frank", "tags": ["a"]}"#).unwrap();
+/// let patch = parse_complete(
+///     r#"[
+///         {"op": "test", "path": "/name", "value": "Frank"},
+///         {"op": "add", "path": "/tags/-", "value": "b"},
+///         {"op": "move", "from": "/name", "path": "/first_name"}
+///     ]"#,
+/// )
+/// .unwrap();
+/// let patch = Patch::from_json(&patch).unwrap();
+/// patch.apply(&mut json).unwrap();
+/// assert_eq!(json.to_string(), r#"{"tags": ["a", "b"], "first_name": "Frank"}"#);
+/// ```
+#[derive(PartialEq, Clone, Default, Debug)]
+pub struct Patch<'a>(Vec<PatchOperation<'a>>);
+
+/// A single operation of a [`Patch`](struct.Patch.html).
+#[derive(PartialEq, Clone, Debug)]
+pub enum PatchOperation<'a> {
+    /// Adds a value to an object or inserts it into an array, see
+    /// [`JsonPointer::insert`](struct.JsonPointer.html#method.insert).
+    Add { path: JsonPointer, value: Json<'a> },
+    /// Removes an existing value.
+    Remove { path: JsonPointer },
+    /// Replaces an existing value.
+    Replace { path: JsonPointer, value: Json<'a> },
+    /// Removes the value at `from` and adds it at `path`.
+    Move {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    /// Adds a copy of the value at `from` at `path`.
+    Copy {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    /// Checks that the value at `path` is equal to `value`. Numbers are compared by value, and
+    /// objects regardless of member order.
+    Test { path: JsonPointer, value: Json<'a> },
+}
+
+/// An error from reading or applying a [`Patch`](struct.Patch.html).
+#[derive(PartialEq, Clone, Debug)]
+pub struct PatchError {
+    index: Option<usize>,
+    kind: PatchErrorKind,
+}
+
+/// The kind of a [`PatchError`](struct.PatchError.html).
+#[derive(PartialEq, Clone, Debug)]
+pub enum PatchErrorKind {
+    /// The patch document is not an array.
+    NotAnArray,
+    /// An operation is not an object.
+    NotAnObject,
+    /// A member of an operation is missing, or `op`, `path` or `from` is not a string.
+    InvalidMember(&'static str),
+    /// The `op` member names an unknown operation.
+    UnknownOperation(String),
+    /// A `path` or `from` member is not a valid pointer, or does not resolve.
+    Pointer(PointerError),
+    /// A `test` operation found a different value at the path.
+    TestFailed(JsonPointer),
+    /// A `move` operation tries to move a value into one of its children.
+    MoveIntoChild(JsonPointer),
+}
+
+impl<'a> Patch<'a> {
+    /// Creates a patch from its operations.
+    pub fn new(operations: Vec<PatchOperation<'a>>) -> Self {
+        Patch(operations)
+    }
+
+    /// Reads a patch from a parsed JSON Patch document.
+    pub fn from_json(json: &Json<'a>) -> Result<Self, PatchError> {
+        let operations = json
+            .as_array()
+            .ok_or_else(|| PatchError::new(None, PatchErrorKind::NotAnArray))?;
+        operations
+            .iter()
+            .enumerate()
+            .map(|(index, op)| {
+                PatchOperation::from_json(op).map_err(|kind| PatchError::new(Some(index), kind))
+            })
+            .collect::<Result<_, _>>()
+            .map(Patch)
+    }
+
+    /// Returns the operations of the patch.
+    pub fn operations(&self) -> &[PatchOperation<'a>] {
+        &self.0
+    }
+
+    /// Returns the patch as a JSON Patch document.
+    pub fn to_json(&self) -> Json<'a> {
+        self.0
+            .iter()
+            .map(PatchOperation::to_json)
+            .collect::<JsonValue>()
+            .into()
+    }
+
+    /// Applies the operations to `json` in order.
+    ///
+    /// Application is atomic: if an operation fails, `json` is left unchanged and the error
+    /// reports the index of the failed operation.
+    pub fn apply(&self, json: &mut Json<'a>) -> Result<(), PatchError> {
+        let mut patched = json.clone();
+        for (index, op) in self.0.iter().enumerate() {
+            op.apply(&mut patched)
+                .map_err(|kind| PatchError::new(Some(index), kind))?;
+        }
+        *json = patched;
+        Ok(())
+    }
+}
+
+impl<'a> PatchOperation<'a> {
+    fn from_json(json: &Json<'a>) -> Result<Self, PatchErrorKind> {
+        if json.as_object().is_none() {
+            return Err(PatchErrorKind::NotAnObject);
+        }
+        let member = |name| json.get(name).ok_or(PatchErrorKind::InvalidMember(name));
+        let pointer = |name| {
+            member(name)?
+                .as_str()
+                .ok_or(PatchErrorKind::InvalidMember(name))?
+                .parse::<JsonPointer>()
+                .map_err(PatchErrorKind::Pointer)
+        };
+        let op = member("op")?
+            .as_str()
+            .ok_or(PatchErrorKind::InvalidMember("op"))?;
+        let path = pointer("path")?;
+        Ok(match op {
+            "add" => PatchOperation::Add {
+                path,
+                value: member("value")?.clone(),
+            },
+            "remove" => PatchOperation::Remove { path },
+            "replace" => PatchOperation::Replace {
+                path,
+                value: member("value")?.clone(),
+            },
+            "move" => PatchOperation::Move {
+                from: pointer("from")?,
+                path,
+            },
+            "copy" => PatchOperation::Copy {
+                from: pointer("from")?,
+                path,
+            },
+            "test" => PatchOperation::Test {
+                path,
+                value: member("value")?.clone(),
+            },
+            op => return Err(PatchErrorKind::UnknownOperation(op.to_string())),
+        })
+    }
+
+    /// Returns the operation as a member of a JSON Patch document.
+    pub fn to_json(&self) -> Json<'a> {
+        let (op, path, from, value) = match self {
+            PatchOperation::Add { path, value } => ("add", path, None, Some(value)),
+            PatchOperation::Remove { path } => ("remove", path, None, None),
+            PatchOperation::Replace { path, value } => ("replace", path, None, Some(value)),
+            PatchOperation::Move { from, path } => ("move", path, Some(from), None),
+            PatchOperation::Copy { from, path } => ("copy", path, Some(from), None),
+            PatchOperation::Test { path, value } => ("test", path, None, Some(value)),
+        };
+        let mut json = Map::new();
+        json.insert("op", op);
+        if let Some(from) = from {
+            json.insert("from", from.to_string());
+        }
+        json.insert("path", path.to_string());
+        if let Some(value) = value {
+            json.insert("value", value.clone());
+        }
+        json.into()
+    }
+
+    fn apply(&self, json: &mut Json<'a>) -> Result<(), PatchErrorKind> {
+        match self {
+            PatchOperation::Add { path, value } => {
+                path.insert(json, value.clone())?;
+            }
+            PatchOperation::Remove { path } => {
+                path.remove(json)?;
+            }
+            PatchOperation::Replace { path, value } => {
+                *path.get_mut(json)? = value.clone();
+            }
+            PatchOperation::Move { from, path } if from == path => {
+                from.get(json)?;
+            }
+            PatchOperation::Move { from, path } => {
+                if path.segments().starts_with(from.segments()) {
+                    return Err(PatchErrorKind::MoveIntoChild(path.clone()));
+                }
+                let value = from.remove(json)?;
+                path.insert(json, value)?;
+            }
+            PatchOperation::Copy { from, path } => {
+                let value = from.get(json)?.clone();
+                path.insert(json, value)?;
+            }
+            PatchOperation::Test { path, value } => {
+                if !equal(path.get(json)?, value) {
+                    return Err(PatchErrorKind::TestFailed(path.clone()));
+                }
+            }
+        };
+        Ok(())
+    }
+}
+
+impl From<PointerError> for PatchErrorKind {
+    fn from(err: PointerError) -> Self {
+        PatchErrorKind::Pointer(err)
+    }
+}
+
+impl PatchError {
+    fn new(index: Option<usize>, kind: PatchErrorKind) -> Self {
+        PatchError { index, kind }
+    }
+
+    /// Returns the index of the operation that is invalid or failed to apply, or `None` if the
+    /// patch document is not an array.
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> &PatchErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for PatchErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            PatchErrorKind::NotAnArray => f.write_str("patch is not an array"),
+            PatchErrorKind::NotAnObject => f.write_str("operation is not an object"),
+            PatchErrorKind::InvalidMember(name) => {
+                write!(f, "missing or invalid member \"{}\"", name)
+            }
+            PatchErrorKind::UnknownOperation(op) => write!(f, "unknown operation {:?}", op),
+            PatchErrorKind::Pointer(err) => err.fmt(f),
+            PatchErrorKind::TestFailed(path) => write!(f, "test failed at \"{}\"", path),
+            PatchErrorKind::MoveIntoChild(path) => {
+                write!(f, "cannot move a value into its child \"{}\"", path)
+            }
+        }
+    }
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        if let Some(index) = self.index {
+            write!(f, "patch operation {}: ", index)?;
+        }
+        self.kind.fmt(f)
+    }
+}
+
+impl std::error::Error for PatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            PatchErrorKind::Pointer(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Json<'a> {
+    /// Applies a JSON Patch atomically. See [`Patch::apply`](struct.Patch.html#method.apply).
+    pub fn apply_patch(&mut self, patch: &Patch<'a>) -> Result<(), PatchError> {
+        patch.apply(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_complete;
+    use assert_matches::assert_matches;
+
+    fn patch(json: &str) -> Result<Patch<'_>, PatchError> {
+        Patch::from_json(&parse_complete(json).unwrap())
+    }
+
+    fn apply<'a>(doc: &'a str, ops: &'a str) -> Result<String, PatchError> {
+        let mut json = parse_complete(doc).unwrap();
+        json.apply_patch(&patch(ops)?)?;
+        Ok(json.to_string())
+    }
+
+    #[test]
+    fn test_rfc_examples() {
+        let cases = [
+            (
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "add", "path": "/baz", "value": "qux"}]"#,
+                r#"{"foo": "bar", "baz": "qux"}"#,
+            ),
+            (
+                r#"{"foo": ["bar", "baz"]}"#,
+                r#"[{"op": "add", "path": "/foo/1", "value": "qux"}]"#,
+                r#"{"foo": ["bar", "qux", "baz"]}"#,
+            ),
+            (
+                r#"{"baz": "qux", "foo": "bar"}"#,
+                r#"[{"op": "remove", "path": "/baz"}]"#,
+                r#"{"foo": "bar"}"#,
+            ),
+            (
+                r#"{"foo": ["bar", "qux", "baz"]}"#,
+                r#"[{"op": "remove", "path": "/foo/1"}]"#,
+                r#"{"foo": ["bar", "baz"]}"#,
+            ),
+            (
+                r#"{"baz": "qux", "foo": "bar"}"#,
+                r#"[{"op": "replace", "path": "/baz", "value": "boo"}]"#,
+                r#"{"baz": "boo", "foo": "bar"}"#,
+            ),
+            (
+                r#"{"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}"#,
+                r#"[{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]"#,
+                r#"{"foo": {"bar": "baz"}, "qux": {"corge": "grault", "thud": "fred"}}"#,
+            ),
+            (
+                r#"{"foo": ["all", "grass", "cows", "eat"]}"#,
+                r#"[{"op": "move", "from": "/foo/1", "path": "/foo/3"}]"#,
+                r#"{"foo": ["all", "cows", "eat", "grass"]}"#,
+            ),
+            (
+                r#"{"baz": "qux", "foo": ["a", 2, "c"]}"#,
+                r#"[{"op": "test", "path": "/baz", "value": "qux"},
+                    {"op": "test", "path": "/foo/1", "value": 2}]"#,
+                r#"{"baz": "qux", "foo": ["a", 2, "c"]}"#,
+            ),
+            (
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "add", "path": "/child", "value": {"grandchild": {}}}]"#,
+                r#"{"foo": "bar", "child": {"grandchild": {}}}"#,
+            ),
+            (
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "add", "path": "/baz", "value": "qux", "xyz": 123}]"#,
+                r#"{"foo": "bar", "baz": "qux"}"#,
+            ),
+            (
+                r#"{"foo": ["bar"]}"#,
+                r#"[{"op": "add", "path": "/foo/-", "value": ["abc", "def"]}]"#,
+                r#"{"foo": ["bar", ["abc", "def"]]}"#,
+            ),
+            (
+                r#"{"/": 9, "~1": 10}"#,
+                r#"[{"op": "test", "path": "/~01", "value": 10}]"#,
+                r#"{"/": 9, "~1": 10}"#,
+            ),
+            (
+                r#"{"foo": {"bar": [1]}}"#,
+                r#"[{"op": "copy", "from": "/foo/bar", "path": "/baz"},
+                    {"op": "add", "path": "/baz/-", "value": 2}]"#,
+                r#"{"foo": {"bar": [1]}, "baz": [1, 2]}"#,
+            ),
+            (
+                "[1, 2]",
+                r#"[{"op": "replace", "path": "", "value": {"a": null}}]"#,
+                r#"{"a": null}"#,
+            ),
+        ];
+        for (doc, ops, expected) in cases.iter() {
+            assert_eq!(apply(doc, ops), Ok(expected.to_string()), "{}", ops);
+        }
+    }
+
+    #[test]
+    fn test_test_equality() {
+        let doc = r#"{"n": 1, "big": 18446744073709551615, "obj": {"a": [1.0, "x"], "b": null}}"#;
+        let ok = [
+            r#"[{"op": "test", "path": "/n", "value": 1.0}]"#,
+            r#"[{"op": "test", "path": "/n", "value": 1e0}]"#,
+            r#"[{"op": "test", "path": "/big", "value": 18446744073709551615}]"#,
+            r#"[{"op": "test", "path": "/obj", "value": {"b": null, "a": [1, "x"]}}]"#,
+        ];
+        for ops in ok.iter() {
+            assert!(apply(doc, ops).is_ok(), "{}", ops);
+        }
+        let failed = [
+            r#"[{"op": "test", "path": "/n", "value": "1"}]"#,
+            r#"[{"op": "test", "path": "/big", "value": 18446744073709551614}]"#,
+            r#"[{"op": "test", "path": "/obj", "value": {"a": [1, "x"]}}]"#,
+            r#"[{"op": "test", "path": "/obj/b", "value": false}]"#,
+        ];
+        for ops in failed.iter() {
+            assert_matches!(
+                apply(doc, ops).unwrap_err().kind(),
+                PatchErrorKind::TestFailed(_)
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_json() {
+        let ops = r#"[{"op": "add", "path": "/a~1b", "value": [1]}, {"op": "remove", "path": ""}, {"op": "replace", "path": "/0", "value": null}, {"op": "move", "from": "/a", "path": "/b"}, {"op": "copy", "from": "/b", "path": "/a"}, {"op": "test", "path": "/a", "value": {}}]"#;
+        assert_eq!(patch(ops).unwrap().to_json().to_string(), ops);
+    }
+
+    #[test]
+    fn test_atomic() {
+        let doc = r#"{"foo": "bar", "list": [1]}"#;
+        let mut json = parse_complete(doc).unwrap();
+        let ops = patch(
+            r#"[{"op": "add", "path": "/baz", "value": "qux"},
+                {"op": "remove", "path": "/list/0"},
+                {"op": "test", "path": "/foo", "value": "baz"},
+                {"op": "add", "path": "/never", "value": 1}]"#,
+        )
+        .unwrap();
+        let err = json.apply_patch(&ops).unwrap_err();
+        assert_eq!(err.index(), Some(2));
+        assert_eq!(
+            err.kind(),
+            &PatchErrorKind::TestFailed("/foo".parse().unwrap())
+        );
+        assert_eq!(
+            err.to_string(),
+            "patch operation 2: test failed at \"/foo\""
+        );
+        assert_eq!(json.to_string(), doc);
+    }
+
+    #[test]
+    fn test_errors() {
+        let doc = r#"{"foo": {"bar": 1}, "list": [1]}"#;
+        let err = |ops| apply(doc, ops).unwrap_err();
+        assert_eq!(err("{}"), PatchError::new(None, PatchErrorKind::NotAnArray));
+        assert_eq!(
+            err("[[]]"),
+            PatchError::new(Some(0), PatchErrorKind::NotAnObject)
+        );
+        assert_eq!(
+            err(r#"[{"op": "remove", "path": "/foo"}, {"path": "/foo"}]"#),
+            PatchError::new(Some(1), PatchErrorKind::InvalidMember("op"))
+        );
+        assert_eq!(
+            err(r#"[{"op": "add", "path": "/foo"}]"#).kind(),
+            &PatchErrorKind::InvalidMember("value")
+        );
+        assert_eq!(
+            err(r#"[{"op": "copy", "from": 1, "path": "/foo"}]"#).kind(),
+            &PatchErrorKind::InvalidMember("from")
+        );
+        assert_eq!(
+            err(r#"[{"op": "update", "path": "/foo"}]"#).kind(),
+            &PatchErrorKind::UnknownOperation("update".into())
+        );
+        assert_eq!(
+            err(r#"[{"op": "remove", "path": "foo"}]"#).kind(),
+            &PatchErrorKind::Pointer(PointerError::InvalidSyntax("foo".into()))
+        );
+        assert_matches!(
+            err(r#"[{"op": "add", "path": "/list/5", "value": 1}]"#).kind(),
+            PatchErrorKind::Pointer(PointerError::IndexOutOfBounds(_))
+        );
+        assert_matches!(
+            err(r#"[{"op": "replace", "path": "/baz", "value": 1}]"#).kind(),
+            PatchErrorKind::Pointer(PointerError::MissingKey(_))
+        );
+        assert_matches!(
+            err(r#"[{"op": "move", "from": "/foo", "path": "/foo/bar/baz"}]"#).kind(),
+            PatchErrorKind::MoveIntoChild(_)
+        );
+        assert_eq!(
+            apply(doc, r#"[{"op": "move", "from": "/foo", "path": "/foo"}]"#),
+            Ok(doc.to_string())
+        );
+    }
+}
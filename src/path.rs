@@ -0,0 +1,650 @@
+use crate::error::{JsonError, JsonParseError};
+use crate::options::ParserOptions;
+use crate::parser::{
+    parse_false, parse_int_part, parse_null, parse_number, parse_quoted, parse_true, ParserResult,
+};
+use crate::repr::{as_integer, equal, is_number, Json, JsonValue};
+use crate::utils::wrap_ws;
+
+use nom::branch::alt;
+use nom::bytes::complete::*;
+use nom::character::complete::*;
+use nom::combinator::*;
+use nom::multi::{many0, separated_nonempty_list};
+use nom::sequence::*;
+use nom::Err::{Error, Failure, Incomplete};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+/// A compiled JSONPath expression, following the syntax of RFC 9535.
+///
+/// Supported are the root `$`, child segments such as `.name`, `['name']`, `[0]`, `[-1]`, `[*]`
+/// and `[1:5:2]`, descendant segments such as `..name` and `..[*]`, unions of selectors such as
+/// `['a', 'b', 0]`, and filters such as `[?@.price < 10 && @.tags]`. Filters compare singular
+/// queries and literals with `==`, `!=`, `<`, `<=`, `>` and `>=`, test for existence, and
+/// combine with `&&`, `||`, `!` and parentheses. The older `?(...)` form is accepted since the
+/// parentheses are part of the expression. Function extensions are not supported.
+///
+/// A comparison with a query that selects no node or more than one node compares "nothing",
+/// which is only equal to nothing.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{parse_complete, JsonPath};
+///
+/// let json = parse_complete(
+///     r#"{"store": {"book": [
+///         {"author": "Rees", "price": 8.95},
+///         {"author": "Waugh", "price": 12.99},
+///         {"author": "Tolkien", "price": 22.99, "isbn": "0-395-19395-8"}
+///     ]}}"#,
+/// )
+/// .unwrap();
+///
+/// let authors = JsonPath::parse("$.store.book[*].author").unwrap();
+/// let authors = authors.query(&json).into_iter().filter_map(|author| author.as_str());
+/// assert_eq!(authors.collect::<Vec<_>>(), vec!["Rees", "Waugh", "Tolkien"]);
+///
+/// let books = &json["store"]["book"];
+/// let prices = json.query("$..book[?@.price < 10 || @.isbn].price").unwrap();
+/// assert_eq!(prices, vec![&books[0]["price"], &books[2]["price"]]);
+///
+/// let err = JsonPath::parse("$.store[?@.price <]").unwrap_err();
+/// assert_eq!(err.to_string().lines().next(), Some("expected a literal or query at line 1, column 19"));
+/// ```
+#[derive(PartialEq, Clone, Debug)]
+pub struct JsonPath<'p>(Query<'p>);
+
+/// A query starting at the root `$` or, inside a filter, at the current node `@`.
+#[derive(PartialEq, Clone, Debug)]
+struct Query<'p> {
+    absolute: bool,
+    segments: Vec<Segment<'p>>,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum Segment<'p> {
+    /// Applies the selectors to the children of each node.
+    Child(Vec<Selector<'p>>),
+    /// Applies the selectors to the children of each node and of all its descendants.
+    Descendant(Vec<Selector<'p>>),
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum Selector<'p> {
+    Name(Cow<'p, str>),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Filter(Filter<'p>),
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum Filter<'p> {
+    Or(Vec<Filter<'p>>),
+    And(Vec<Filter<'p>>),
+    Not(Box<Filter<'p>>),
+    Exists(Query<'p>),
+    Compare(Comparable<'p>, Comparison, Comparable<'p>),
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum Comparable<'p> {
+    Literal(Json<'p>),
+    Query(Query<'p>),
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl<'p> JsonPath<'p> {
+    /// Parses a JSONPath expression. Errors are located in `input`.
+    pub fn parse(input: &'p str) -> Result<Self, JsonError<'p>> {
+        match all_consuming(parse_path::<JsonError>)(input) {
+            Ok((_, path)) => Ok(path),
+            Err(Error(err)) | Err(Failure(err)) => Err(err.locate(input)),
+            Err(Incomplete(_)) => {
+                Err(JsonError::from_expected(&input[input.len()..], "more input").locate(input))
+            }
+        }
+    }
+
+    /// Returns the nodes selected by the expression in `json`, in document order.
+    pub fn query<'j, 'a>(&self, json: &'j Json<'a>) -> Vec<&'j Json<'a>> {
+        self.0.select(json, json)
+    }
+}
+
+impl<'a> Json<'a> {
+    /// Parses a JSONPath expression and returns the nodes it selects. See
+    /// [`JsonPath`](struct.JsonPath.html).
+    pub fn query<'j, 'p>(&'j self, path: &'p str) -> Result<Vec<&'j Json<'a>>, JsonError<'p>> {
+        Ok(JsonPath::parse(path)?.query(self))
+    }
+}
+
+impl Query<'_> {
+    fn select<'j, 'a>(&self, current: &'j Json<'a>, root: &'j Json<'a>) -> Vec<&'j Json<'a>> {
+        let start = if self.absolute { root } else { current };
+        self.segments
+            .iter()
+            .fold(vec![start], |nodes, segment| segment.select(nodes, root))
+    }
+}
+
+impl Segment<'_> {
+    fn select<'j, 'a>(&self, nodes: Vec<&'j Json<'a>>, root: &'j Json<'a>) -> Vec<&'j Json<'a>> {
+        let (selectors, nodes) = match self {
+            Segment::Child(selectors) => (selectors, nodes),
+            Segment::Descendant(selectors) => {
+                let mut descendants = Vec::new();
+                for node in nodes {
+                    descendants_of(node, &mut descendants);
+                }
+                (selectors, descendants)
+            }
+        };
+        let mut selected = Vec::new();
+        for node in nodes {
+            for selector in selectors {
+                selector.select(node, root, &mut selected);
+            }
+        }
+        selected
+    }
+}
+
+/// Collects `node` and all its descendants in document order.
+fn descendants_of<'j, 'a>(node: &'j Json<'a>, descendants: &mut Vec<&'j Json<'a>>) {
+    descendants.push(node);
+    for child in children(node) {
+        descendants_of(child, descendants);
+    }
+}
+
+fn children<'j, 'a>(node: &'j Json<'a>) -> Vec<&'j Json<'a>> {
+    match node.as_value() {
+        Some(JsonValue::Array(arr)) => arr.iter().collect(),
+        Some(JsonValue::Object(obj)) => obj.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl Selector<'_> {
+    fn select<'j, 'a>(
+        &self,
+        node: &'j Json<'a>,
+        root: &'j Json<'a>,
+        selected: &mut Vec<&'j Json<'a>>,
+    ) {
+        match self {
+            Selector::Name(name) => selected.extend(node.get(name)),
+            Selector::Wildcard => selected.extend(children(node)),
+            Selector::Index(index) => {
+                if let Some(arr) = node.as_array() {
+                    let len = arr.len() as i64;
+                    let index = if *index < 0 { len + index } else { *index };
+                    selected.extend(usize::try_from(index).ok().and_then(|i| arr.get(i)));
+                }
+            }
+            Selector::Slice(start, end, step) => {
+                if let Some(arr) = node.as_array() {
+                    selected.extend(
+                        slice(arr.len() as i64, *start, *end, step.unwrap_or(1)).map(|i| &arr[i]),
+                    );
+                }
+            }
+            Selector::Filter(filter) => selected.extend(
+                children(node)
+                    .into_iter()
+                    .filter(|child| filter.test(child, root)),
+            ),
+        }
+    }
+}
+
+/// Returns the indices selected by a slice of an array of length `len`, as defined in RFC 9535.
+fn slice(len: i64, start: Option<i64>, end: Option<i64>, step: i64) -> impl Iterator<Item = usize> {
+    let normalize = |index: i64| if index < 0 { len + index } else { index };
+    let (mut index, bound) = if step >= 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        (lower, upper)
+    } else {
+        let upper = start.map_or(len - 1, normalize).clamp(-1, len - 1);
+        let lower = end.map_or(-1, normalize).clamp(-1, len - 1);
+        (upper, lower)
+    };
+    std::iter::from_fn(move || {
+        let in_range = match step {
+            0 => false,
+            step if step > 0 => index < bound,
+            _ => index > bound,
+        };
+        if in_range {
+            let current = index as usize;
+            index = index.saturating_add(step);
+            Some(current)
+        } else {
+            None
+        }
+    })
+}
+
+impl Filter<'_> {
+    fn test(&self, node: &Json<'_>, root: &Json<'_>) -> bool {
+        match self {
+            Filter::Or(filters) => filters.iter().any(|filter| filter.test(node, root)),
+            Filter::And(filters) => filters.iter().all(|filter| filter.test(node, root)),
+            Filter::Not(filter) => !filter.test(node, root),
+            Filter::Exists(query) => !query.select(node, root).is_empty(),
+            Filter::Compare(lhs, comparison, rhs) => {
+                let (lhs, rhs) = (lhs.value(node, root), rhs.value(node, root));
+                match comparison {
+                    Comparison::Eq => equal_or_nothing(lhs, rhs),
+                    Comparison::Ne => !equal_or_nothing(lhs, rhs),
+                    Comparison::Lt => less(lhs, rhs),
+                    Comparison::Le => less(lhs, rhs) || equal_or_nothing(lhs, rhs),
+                    Comparison::Gt => less(rhs, lhs),
+                    Comparison::Ge => less(rhs, lhs) || equal_or_nothing(lhs, rhs),
+                }
+            }
+        }
+    }
+}
+
+impl Comparable<'_> {
+    /// Returns the value to compare, or `None` for nothing.
+    fn value<'j>(&'j self, node: &'j Json<'j>, root: &'j Json<'j>) -> Option<&'j Json<'j>> {
+        match self {
+            Comparable::Literal(json) => Some(json),
+            Comparable::Query(query) => match query.select(node, root).as_slice() {
+                [value] => Some(value),
+                _ => None,
+            },
+        }
+    }
+}
+
+fn equal_or_nothing(lhs: Option<&Json<'_>>, rhs: Option<&Json<'_>>) -> bool {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => equal(lhs, rhs),
+        (lhs, rhs) => lhs.is_none() && rhs.is_none(),
+    }
+}
+
+/// Orders numbers by value and strings by code point. Other values are never ordered.
+fn less(lhs: Option<&Json<'_>>, rhs: Option<&Json<'_>>) -> bool {
+    match (lhs.and_then(Json::as_value), rhs.and_then(Json::as_value)) {
+        (Some(JsonValue::String(lhs)), Some(JsonValue::String(rhs))) => lhs < rhs,
+        (Some(lhs), Some(rhs)) if is_number(lhs) && is_number(rhs) => {
+            match (as_integer(lhs), as_integer(rhs)) {
+                (Some(lhs), Some(rhs)) => lhs < rhs,
+                _ => lhs.as_f64() < rhs.as_f64(),
+            }
+        }
+        _ => false,
+    }
+}
+
+fn parse_path<'p, E: JsonParseError<'p>>(input: &'p str) -> ParserResult<'p, JsonPath<'p>, E> {
+    let (input, _) = char('$')(input)?;
+    let (input, segments) = parse_segments(input)?;
+    let (input, _) = multispace0(input)?;
+    Ok((
+        input,
+        JsonPath(Query {
+            absolute: true,
+            segments,
+        }),
+    ))
+}
+
+fn parse_segments<'p, E: JsonParseError<'p>>(
+    input: &'p str,
+) -> ParserResult<'p, Vec<Segment<'p>>, E> {
+    many0(preceded(multispace0, parse_segment))(input)
+}
+
+fn parse_segment<'p, E: JsonParseError<'p>>(input: &'p str) -> ParserResult<'p, Segment<'p>, E> {
+    let shorthand = |input| {
+        alt((
+            map(char('*'), |_| vec![Selector::Wildcard]),
+            map(parse_member_name, |name| vec![Selector::Name(name.into())]),
+        ))(input)
+        .map_err(|err| match err {
+            Error(_) => Failure(E::from_expected(input, "a member name")),
+            err => err,
+        })
+    };
+    alt((
+        map(
+            preceded(tag(".."), alt((parse_bracketed, shorthand))),
+            Segment::Descendant,
+        ),
+        map(parse_bracketed, Segment::Child),
+        map(preceded(char('.'), shorthand), Segment::Child),
+    ))(input)
+}
+
+fn parse_member_name<'p, E: JsonParseError<'p>>(input: &'p str) -> ParserResult<'p, &'p str, E> {
+    verify(
+        take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()),
+        |name: &str| !name.starts_with(|c: char| c.is_ascii_digit()),
+    )(input)
+}
+
+fn parse_bracketed<'p, E: JsonParseError<'p>>(
+    input: &'p str,
+) -> ParserResult<'p, Vec<Selector<'p>>, E> {
+    preceded(
+        char('['),
+        cut(terminated(
+            separated_nonempty_list(char(','), wrap_ws(parse_selector)),
+            char(']'),
+        )),
+    )(input)
+}
+
+fn parse_selector<'p, E: JsonParseError<'p>>(input: &'p str) -> ParserResult<'p, Selector<'p>, E> {
+    alt((
+        map(|input| parse_quoted(input, '\''), Selector::Name),
+        map(|input| parse_quoted(input, '"'), Selector::Name),
+        map(char('*'), |_| Selector::Wildcard),
+        parse_slice,
+        map(parse_int, Selector::Index),
+        map(
+            preceded(pair(char('?'), multispace0), cut(parse_or)),
+            Selector::Filter,
+        ),
+    ))(input)
+    .map_err(|err| match err {
+        Error(_) => Error(E::from_expected(input, "a selector")),
+        err => err,
+    })
+}
+
+fn parse_slice<'p, E: JsonParseError<'p>>(input: &'p str) -> ParserResult<'p, Selector<'p>, E> {
+    let colon = |input| wrap_ws(char(':'))(input);
+    map(
+        tuple((
+            opt(parse_int),
+            colon,
+            opt(parse_int),
+            opt(preceded(colon, opt(parse_int))),
+        )),
+        |(start, _, end, step)| Selector::Slice(start, end, step.flatten()),
+    )(input)
+}
+
+fn parse_int<'p, E: JsonParseError<'p>>(input: &'p str) -> ParserResult<'p, i64, E> {
+    peek(pair(opt(char('-')), digit1))(input)?;
+    let (rest, int) = recognize(pair(opt(char('-')), parse_int_part))(input)?;
+    match int.parse() {
+        Ok(int) => Ok((rest, int)),
+        Err(_) => Err(Failure(E::from_expected(input, "an integer in range"))),
+    }
+}
+
+fn parse_or<'p, E: JsonParseError<'p>>(input: &'p str) -> ParserResult<'p, Filter<'p>, E> {
+    map(
+        separated_nonempty_list(wrap_ws(tag("||")), parse_and),
+        |mut filters| match filters.len() {
+            1 => filters.pop().unwrap(),
+            _ => Filter::Or(filters),
+        },
+    )(input)
+}
+
+fn parse_and<'p, E: JsonParseError<'p>>(input: &'p str) -> ParserResult<'p, Filter<'p>, E> {
+    map(
+        separated_nonempty_list(wrap_ws(tag("&&")), parse_basic),
+        |mut filters| match filters.len() {
+            1 => filters.pop().unwrap(),
+            _ => Filter::And(filters),
+        },
+    )(input)
+}
+
+fn parse_basic<'p, E: JsonParseError<'p>>(input: &'p str) -> ParserResult<'p, Filter<'p>, E> {
+    let not = |input| pair(char('!'), multispace0)(input);
+    alt((
+        parse_paren,
+        map(preceded(not, parse_paren), |filter| {
+            Filter::Not(Box::new(filter))
+        }),
+        map(
+            tuple((
+                parse_comparable,
+                wrap_ws(parse_comparison),
+                cut(parse_comparable),
+            )),
+            |(lhs, comparison, rhs)| Filter::Compare(lhs, comparison, rhs),
+        ),
+        map(preceded(not, parse_query), |query| {
+            Filter::Not(Box::new(Filter::Exists(query)))
+        }),
+        map(parse_query, Filter::Exists),
+    ))(input)
+    .map_err(|err| match err {
+        Error(_) => Error(E::from_expected(input, "a filter expression")),
+        err => err,
+    })
+}
+
+fn parse_paren<'p, E: JsonParseError<'p>>(input: &'p str) -> ParserResult<'p, Filter<'p>, E> {
+    preceded(
+        pair(char('('), multispace0),
+        cut(terminated(parse_or, pair(multispace0, char(')')))),
+    )(input)
+}
+
+fn parse_comparison<'p, E: JsonParseError<'p>>(input: &'p str) -> ParserResult<'p, Comparison, E> {
+    alt((
+        map(tag("=="), |_| Comparison::Eq),
+        map(tag("!="), |_| Comparison::Ne),
+        map(tag("<="), |_| Comparison::Le),
+        map(tag(">="), |_| Comparison::Ge),
+        map(char('<'), |_| Comparison::Lt),
+        map(char('>'), |_| Comparison::Gt),
+    ))(input)
+}
+
+fn parse_comparable<'p, E: JsonParseError<'p>>(
+    input: &'p str,
+) -> ParserResult<'p, Comparable<'p>, E> {
+    alt((
+        map(parse_null, Comparable::Literal),
+        map(parse_true, Comparable::Literal),
+        map(parse_false, Comparable::Literal),
+        map(
+            |input| parse_number(input, &ParserOptions::default()),
+            Comparable::Literal,
+        ),
+        map(
+            alt((
+                |input| parse_quoted(input, '\''),
+                |input| parse_quoted(input, '"'),
+            )),
+            |string| Comparable::Literal(string.into()),
+        ),
+        map(parse_query, Comparable::Query),
+    ))(input)
+    .map_err(|err| match err {
+        Error(_) => Error(E::from_expected(input, "a literal or query")),
+        err => err,
+    })
+}
+
+fn parse_query<'p, E: JsonParseError<'p>>(input: &'p str) -> ParserResult<'p, Query<'p>, E> {
+    let (input, root) = one_of("$@")(input)?;
+    let (input, segments) = parse_segments(input)?;
+    Ok((
+        input,
+        Query {
+            absolute: root == '$',
+            segments,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_complete;
+
+    fn query(json: &str, path: &str) -> String {
+        let json = parse_complete(json).unwrap();
+        let nodes = json.query(path).unwrap();
+        Json::from(nodes.into_iter().cloned().collect::<Vec<_>>()).to_string()
+    }
+
+    const STORE: &str = r#"{
+        "store": {
+            "book": [
+                {"category": "reference", "author": "Nigel Rees", "title": "Sayings of the Century", "price": 8.95},
+                {"category": "fiction", "author": "Evelyn Waugh", "title": "Sword of Honour", "price": 12.99},
+                {"category": "fiction", "author": "Herman Melville", "title": "Moby Dick", "isbn": "0-553-21311-3", "price": 8.99},
+                {"category": "fiction", "author": "J. R. R. Tolkien", "title": "The Lord of the Rings", "isbn": "0-395-19395-8", "price": 22.99}
+            ],
+            "bicycle": {"color": "red", "price": 399}
+        }
+    }"#;
+
+    #[test]
+    fn test_rfc_examples() {
+        let cases = [
+            (
+                "$.store.book[*].author",
+                r#"["Nigel Rees", "Evelyn Waugh", "Herman Melville", "J. R. R. Tolkien"]"#,
+            ),
+            (
+                "$..author",
+                r#"["Nigel Rees", "Evelyn Waugh", "Herman Melville", "J. R. R. Tolkien"]"#,
+            ),
+            ("$.store.*.color", r#"["red"]"#),
+            ("$.store..price", "[8.95, 12.99, 8.99, 22.99, 399]"),
+            ("$..book[2].title", r#"["Moby Dick"]"#),
+            ("$..book[-1].title", r#"["The Lord of the Rings"]"#),
+            ("$..book[0,1].price", "[8.95, 12.99]"),
+            ("$..book[:2].price", "[8.95, 12.99]"),
+            ("$..book[?@.isbn].price", "[8.99, 22.99]"),
+            ("$..book[?@.price<10].price", "[8.95, 8.99]"),
+            (
+                "$..book[?(@.price > $.store.book[0].price)].price",
+                "[12.99, 8.99, 22.99]",
+            ),
+            ("$..*.color", r#"["red"]"#),
+            (
+                "$['store'][\"bicycle\"]",
+                r#"[{"color": "red", "price": 399}]"#,
+            ),
+            ("$.store.bicycle[?@ == 'red']", r#"["red"]"#),
+        ];
+        for (path, expected) in cases.iter() {
+            assert_eq!(query(STORE, path), *expected, "{}", path);
+        }
+        assert_eq!(
+            parse_complete(STORE).unwrap().query("$..*").unwrap().len(),
+            27
+        );
+    }
+
+    #[test]
+    fn test_slices() {
+        let arr = r#"["a", "b", "c", "d", "e", "f", "g"]"#;
+        let cases = [
+            ("$[1:3]", r#"["b", "c"]"#),
+            ("$[5:]", r#"["f", "g"]"#),
+            ("$[1:5:2]", r#"["b", "d"]"#),
+            ("$[5:1:-2]", r#"["f", "d"]"#),
+            ("$[::-1]", r#"["g", "f", "e", "d", "c", "b", "a"]"#),
+            ("$[-2:]", r#"["f", "g"]"#),
+            ("$[:-5]", r#"["a", "b"]"#),
+            ("$[0:100]", r#"["a", "b", "c", "d", "e", "f", "g"]"#),
+            ("$[1:3:0]", "[]"),
+            ("$[ 1 : 3 ]", r#"["b", "c"]"#),
+            ("$[-8]", "[]"),
+            ("$[7]", "[]"),
+            ("$[0, -1, 0]", r#"["a", "g", "a"]"#),
+        ];
+        for (path, expected) in cases.iter() {
+            assert_eq!(query(arr, path), *expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_filters() {
+        let json = r#"{"a": [3, 5, 1, 2, 4, 6, {"b": "j"}, {"b": "k"}, {"b": {}}, {"b": "kilo"}, null, true, 1.0, "1"]}"#;
+        let cases = [
+            ("$.a[?@.b == 'kilo']", r#"[{"b": "kilo"}]"#),
+            ("$.a[?@>3.5]", "[5, 4, 6]"),
+            ("$.a[?@.b]", r#"[{"b": "j"}, {"b": "k"}, {"b": {}}, {"b": "kilo"}]"#),
+            ("$.a[?@ == 1]", "[1, 1.0]"),
+            ("$.a[?@ <= 1 && @ != 1.0]", "[]"),
+            ("$.a[?@ < 2 || @.b == \"k\"]", r#"[1, {"b": "k"}, 1.0]"#),
+            ("$.a[?!(@ > 1) && @ == null]", "[null]"),
+            ("$.a[?!@.b && @ == true]", "[true]"),
+            ("$.a[?@.b > 'j']", r#"[{"b": "k"}, {"b": "kilo"}]"#),
+            ("$.a[?@.c == @.d]", r#"[3, 5, 1, 2, 4, 6, {"b": "j"}, {"b": "k"}, {"b": {}}, {"b": "kilo"}, null, true, 1.0, "1"]"#),
+            ("$.a[?@.b == $.a[6].b]", r#"[{"b": "j"}]"#),
+            ("$[?@[0] == 3]", "[[3, 5, 1, 2, 4, 6, {\"b\": \"j\"}, {\"b\": \"k\"}, {\"b\": {}}, {\"b\": \"kilo\"}, null, true, 1.0, \"1\"]]"),
+        ];
+        for (path, expected) in cases.iter() {
+            assert_eq!(query(json, path), *expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_quoted_names() {
+        let json = r#"{"a'b": 1, "c\"d": 2, "é": 3, "é t": 4}"#;
+        assert_eq!(query(json, r"$['a\'b']"), "[1]");
+        assert_eq!(query(json, r#"$["c\"d", 'c"d']"#), "[2, 2]");
+        assert_eq!(query(json, r"$['é'].é"), "[]");
+        assert_eq!(query(json, r"$.é"), "[3]");
+        assert_eq!(query(json, r"$['é t']"), "[4]");
+    }
+
+    #[test]
+    fn test_references() {
+        let json = parse_complete(r#"{"a": [{"b": 1}, {"b": 2}]}"#).unwrap();
+        let nodes = JsonPath::parse("$..b").unwrap().query(&json);
+        assert!(std::ptr::eq(nodes[1], &json["a"][1]["b"]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = [
+            ("", 1),
+            ("store", 1),
+            ("$.", 3),
+            ("$.1a", 3),
+            ("$[", 3),
+            ("$[1", 4),
+            ("$[01]", 4),
+            ("$['a]", 6),
+            ("$[?@.a == ]", 11),
+            ("$[?(@.a]", 8),
+            ("$[?1]", 4),
+            ("$[99999999999999999999]", 3),
+            ("$.a b", 5),
+        ];
+        for (path, column) in cases.iter() {
+            let err = JsonPath::parse(path).unwrap_err();
+            assert_eq!(
+                err.location().map(|loc| loc.column),
+                Some(*column),
+                "{}: {}",
+                path,
+                err
+            );
+        }
+    }
+}
@@ -0,0 +1,464 @@
+use crate::repr::{Json, JsonValue};
+
+use std::fmt::{Display, Error, Formatter};
+use std::iter::FromIterator;
+use std::mem;
+use std::str::FromStr;
+
+/// A JSON Pointer as defined in RFC 6901, such as `/phone/work` or `/friends/0`.
+///
+/// A pointer is a sequence of reference tokens, stored unescaped. Parsing replaces `~1` with `/`
+/// and `~0` with `~`, and `Display` escapes them back. The empty pointer refers to the whole
+/// document.
+///
+/// When a token is applied to an array it must be a decimal index without leading zeros. The
+/// token `-` refers to the position after the last element, which can only be used to append.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{parse_complete, JsonPointer};
+///
+/// let json = parse_complete(r#"{"a/b": {"m~n": [10, 20]}}"#).unwrap();
+/// let pointer: JsonPointer = "/a~1b/m~0n/1".parse().unwrap();
+/// assert_eq!(pointer.segments(), &["a/b", "m~n", "1"]);
+/// assert_eq!(pointer.get(&json).unwrap().as_i64(), Some(20));
+///
+/// let built: JsonPointer = vec!["a/b", "m~n", "1"].into_iter().collect();
+/// assert_eq!(built, pointer);
+/// assert_eq!(built.to_string(), "/a~1b/m~0n/1");
+/// ```
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Default, Debug)]
+pub struct JsonPointer(Vec<String>);
+
+/// An error from parsing or resolving a [`JsonPointer`](struct.JsonPointer.html).
+///
+/// Resolution errors carry the pointer up to and including the token that could not be resolved.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum PointerError {
+    /// The text is not empty and does not start with `/`, or contains a `~` that is not followed
+    /// by `0` or `1`.
+    InvalidSyntax(String),
+    /// An object has no member with the key.
+    MissingKey(JsonPointer),
+    /// An array has no element at the index. This includes the `-` token, except when adding.
+    IndexOutOfBounds(JsonPointer),
+    /// A token applied to an array is not a valid index, such as `01` or `name`.
+    InvalidIndex(JsonPointer),
+    /// A token is applied to a value that is neither an object nor an array.
+    NotAContainer(JsonPointer),
+    /// The pointer of a [`Cst`](struct.Cst.html) refers to the values of a repeated key collected
+    /// by `DuplicateKeys::Collect`, which are not a single value of the tree.
+    Collected(JsonPointer),
+}
+
+pub(crate) type ErrorFn = fn(JsonPointer) -> PointerError;
+
+impl JsonPointer {
+    /// Creates the empty pointer, which refers to the whole document.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the unescaped reference tokens.
+    pub fn segments(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Returns `true` if this is the empty pointer.
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Appends a reference token.
+    pub fn push<S: Into<String>>(&mut self, segment: S) {
+        self.0.push(segment.into());
+    }
+
+    /// Removes the last reference token and returns it, if any.
+    pub fn pop(&mut self) -> Option<String> {
+        self.0.pop()
+    }
+
+    /// Returns the pointer with a reference token appended.
+    pub fn child<S: Into<String>>(mut self, segment: S) -> Self {
+        self.push(segment);
+        self
+    }
+
+    /// Returns a reference to the value the pointer refers to in `json`.
+    pub fn get<'j, 'a>(&self, json: &'j Json<'a>) -> Result<&'j Json<'a>, PointerError> {
+        self.0
+            .iter()
+            .enumerate()
+            .try_fold(json, |json, (i, segment)| {
+                child(json, segment).map_err(|err| err(self.prefix(i + 1)))
+            })
+    }
+
+    /// Returns a mutable reference to the value the pointer refers to in `json`.
+    pub fn get_mut<'j, 'a>(
+        &self,
+        json: &'j mut Json<'a>,
+    ) -> Result<&'j mut Json<'a>, PointerError> {
+        self.get_prefix_mut(json, self.0.len())
+    }
+
+    /// Adds `value` at the location the pointer refers to, with the semantics of the JSON Patch
+    /// `add` operation, and returns the value it replaces, if any.
+    ///
+    /// The parent of the location must exist. A member of an object is inserted or replaced. An
+    /// element is inserted into an array before the given index, which may be equal to the length
+    /// of the array, or appended if the index is `-`. The empty pointer replaces the document.
+    pub fn insert<'a>(
+        &self,
+        json: &mut Json<'a>,
+        value: Json<'a>,
+    ) -> Result<Option<Json<'a>>, PointerError> {
+        let last = match self.0.last() {
+            Some(last) => last,
+            None => return Ok(Some(mem::replace(json, value))),
+        };
+        match &mut self.get_prefix_mut(json, self.0.len() - 1)?.0 {
+            Some(JsonValue::Object(obj)) => Ok(obj.insert(last.clone(), value)),
+            Some(JsonValue::Array(arr)) => {
+                let index = match last.as_str() {
+                    "-" => arr.len(),
+                    _ => index(last).map_err(|err| err(self.clone()))?,
+                };
+                if index > arr.len() {
+                    return Err(PointerError::IndexOutOfBounds(self.clone()));
+                }
+                arr.insert(index, value);
+                Ok(None)
+            }
+            _ => Err(PointerError::NotAContainer(self.clone())),
+        }
+    }
+
+    /// Removes the value the pointer refers to and returns it. Elements after a removed array
+    /// element are shifted down. The empty pointer replaces the document with `null`.
+    pub fn remove<'a>(&self, json: &mut Json<'a>) -> Result<Json<'a>, PointerError> {
+        let last = match self.0.last() {
+            Some(last) => last,
+            None => return Ok(mem::replace(json, Json(None))),
+        };
+        match &mut self.get_prefix_mut(json, self.0.len() - 1)?.0 {
+            Some(JsonValue::Object(obj)) => obj
+                .remove(last)
+                .ok_or_else(|| PointerError::MissingKey(self.clone())),
+            Some(JsonValue::Array(arr)) => match index(last) {
+                Ok(index) if index < arr.len() => Ok(arr.remove(index)),
+                Ok(_) => Err(PointerError::IndexOutOfBounds(self.clone())),
+                Err(err) => Err(err(self.clone())),
+            },
+            _ => Err(PointerError::NotAContainer(self.clone())),
+        }
+    }
+
+    fn get_prefix_mut<'j, 'a>(
+        &self,
+        json: &'j mut Json<'a>,
+        len: usize,
+    ) -> Result<&'j mut Json<'a>, PointerError> {
+        self.0[..len]
+            .iter()
+            .enumerate()
+            .try_fold(json, |json, (i, segment)| {
+                child_mut(json, segment).map_err(|err| err(self.prefix(i + 1)))
+            })
+    }
+
+    fn prefix(&self, len: usize) -> JsonPointer {
+        JsonPointer(self.0[..len].to_vec())
+    }
+}
+
+pub(crate) fn index(segment: &str) -> Result<usize, ErrorFn> {
+    if segment == "-" {
+        Err(PointerError::IndexOutOfBounds)
+    } else if segment.is_empty()
+        || !segment.bytes().all(|b| b.is_ascii_digit())
+        || (segment.len() > 1 && segment.starts_with('0'))
+    {
+        Err(PointerError::InvalidIndex)
+    } else {
+        // the only way a valid index can fail to parse is by overflowing
+        segment
+            .parse()
+            .map_err(|_| PointerError::IndexOutOfBounds as ErrorFn)
+    }
+}
+
+fn child<'j, 'a>(json: &'j Json<'a>, segment: &str) -> Result<&'j Json<'a>, ErrorFn> {
+    match &json.0 {
+        Some(JsonValue::Object(obj)) => obj.get(segment).ok_or(PointerError::MissingKey),
+        Some(JsonValue::Array(arr)) => arr
+            .get(index(segment)?)
+            .ok_or(PointerError::IndexOutOfBounds),
+        _ => Err(PointerError::NotAContainer),
+    }
+}
+
+fn child_mut<'j, 'a>(json: &'j mut Json<'a>, segment: &str) -> Result<&'j mut Json<'a>, ErrorFn> {
+    match &mut json.0 {
+        Some(JsonValue::Object(obj)) => obj.get_mut(segment).ok_or(PointerError::MissingKey),
+        Some(JsonValue::Array(arr)) => arr
+            .get_mut(index(segment)?)
+            .ok_or(PointerError::IndexOutOfBounds),
+        _ => Err(PointerError::NotAContainer),
+    }
+}
+
+fn unescape(segment: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(segment.len());
+    let mut chars = segment.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next() {
+                Some('0') => unescaped.push('~'),
+                Some('1') => unescaped.push('/'),
+                _ => return None,
+            },
+            c => unescaped.push(c),
+        }
+    }
+    Some(unescaped)
+}
+
+impl FromStr for JsonPointer {
+    type Err = PointerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Ok(JsonPointer::new());
+        }
+        let invalid = || PointerError::InvalidSyntax(s.to_string());
+        s.strip_prefix('/')
+            .ok_or_else(invalid)?
+            .split('/')
+            .map(|segment| unescape(segment).ok_or_else(invalid))
+            .collect()
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for JsonPointer {
+    fn from_iter<T: IntoIterator<Item = S>>(iter: T) -> Self {
+        JsonPointer(iter.into_iter().map(S::into).collect())
+    }
+}
+
+impl Display for JsonPointer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        for segment in &self.0 {
+            write!(f, "/{}", segment.replace('~', "~0").replace('/', "~1"))?;
+        }
+        Ok(())
+    }
+}
+
+impl PointerError {
+    /// Returns the pointer up to the token that could not be resolved, unless this is a syntax
+    /// error.
+    pub fn path(&self) -> Option<&JsonPointer> {
+        match self {
+            PointerError::InvalidSyntax(_) => None,
+            PointerError::MissingKey(path)
+            | PointerError::IndexOutOfBounds(path)
+            | PointerError::InvalidIndex(path)
+            | PointerError::NotAContainer(path)
+            | PointerError::Collected(path) => Some(path),
+        }
+    }
+}
+
+impl Display for PointerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            PointerError::InvalidSyntax(pointer) => write!(f, "invalid JSON pointer {:?}", pointer),
+            PointerError::MissingKey(path) => write!(f, "missing key at \"{}\"", path),
+            PointerError::IndexOutOfBounds(path) => {
+                write!(f, "array index out of bounds at \"{}\"", path)
+            }
+            PointerError::InvalidIndex(path) => write!(f, "invalid array index at \"{}\"", path),
+            PointerError::NotAContainer(path) => {
+                write!(f, "not an object or array at \"{}\"", path)
+            }
+            PointerError::Collected(path) => write!(f, "collected repeated key at \"{}\"", path),
+        }
+    }
+}
+
+impl std::error::Error for PointerError {}
+
+impl<'a> Json<'a> {
+    /// Returns a reference to the value at the given JSON Pointer.
+    ///
+    /// # Example
+    /// ```rust
+    /// use nom_json_parser::{parse_complete, PointerError};
+    ///
+    /// let mut json = parse_complete(r#"{"phone": {"work": "123"}, "tags": ["a"]}"#).unwrap();
+    /// assert_eq!(json.pointer("/phone/work").unwrap().as_str(), Some("123"));
+    ///
+    /// json.pointer_insert("/tags/-", "b").unwrap();
+    /// json.pointer_insert("/phone/fax", None).unwrap();
+    /// assert_eq!(json.pointer_remove("/tags/0").unwrap().as_str(), Some("a"));
+    /// assert_eq!(json.to_string(), r#"{"phone": {"work": "123", "fax": null}, "tags": ["b"]}"#);
+    ///
+    /// let err = json.pointer("/phone/home").unwrap_err();
+    /// assert_eq!(err, PointerError::MissingKey("/phone/home".parse().unwrap()));
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Result<&Json<'a>, PointerError> {
+        pointer.parse::<JsonPointer>()?.get(self)
+    }
+
+    /// Returns a mutable reference to the value at the given JSON Pointer.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Result<&mut Json<'a>, PointerError> {
+        pointer.parse::<JsonPointer>()?.get_mut(self)
+    }
+
+    /// Adds a value at the given JSON Pointer and returns the value it replaces, if any. See
+    /// [`JsonPointer::insert`](struct.JsonPointer.html#method.insert).
+    pub fn pointer_insert<V: Into<Json<'a>>>(
+        &mut self,
+        pointer: &str,
+        value: V,
+    ) -> Result<Option<Json<'a>>, PointerError> {
+        pointer.parse::<JsonPointer>()?.insert(self, value.into())
+    }
+
+    /// Removes the value at the given JSON Pointer and returns it. See
+    /// [`JsonPointer::remove`](struct.JsonPointer.html#method.remove).
+    pub fn pointer_remove(&mut self, pointer: &str) -> Result<Json<'a>, PointerError> {
+        pointer.parse::<JsonPointer>()?.remove(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_complete;
+
+    fn ptr(s: &str) -> JsonPointer {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_display() {
+        assert_eq!(ptr(""), JsonPointer::new());
+        assert_eq!(ptr("/").segments(), &[""]);
+        assert_eq!(ptr("//a/").segments(), &["", "a", ""]);
+        assert_eq!(ptr("/~01").segments(), &["~1"]);
+        assert_eq!(ptr("/a~1b~0").segments(), &["a/b~"]);
+        for s in &["", "/", "/~01", "/a~1b~0/ /%/-/0"] {
+            assert_eq!(ptr(s).to_string(), *s);
+        }
+        for s in &["a", "#/a", "/~", "/~2", "/a~/b"] {
+            assert_eq!(
+                s.parse::<JsonPointer>(),
+                Err(PointerError::InvalidSyntax(s.to_string()))
+            );
+        }
+        let built = JsonPointer::new().child("a").child("0");
+        assert_eq!(built, ptr("/a/0"));
+    }
+
+    #[test]
+    fn test_rfc_examples() {
+        let json = parse_complete(
+            r#"{"foo": ["bar", "baz"], "": 0, "a/b": 1, "c%d": 2, "e^f": 3, "g|h": 4,
+                "i\\j": 5, "k\"l": 6, " ": 7, "m~n": 8}"#,
+        )
+        .unwrap();
+        assert_eq!(json.pointer(""), Ok(&json));
+        assert_eq!(json.pointer("/foo"), Ok(&Json::from(vec!["bar", "baz"])));
+        assert_eq!(json.pointer("/foo/0"), Ok(&Json::from("bar")));
+        let expected = [
+            ("/", 0),
+            ("/a~1b", 1),
+            ("/c%d", 2),
+            ("/e^f", 3),
+            ("/g|h", 4),
+            ("/i\\j", 5),
+            ("/k\"l", 6),
+            ("/ ", 7),
+            ("/m~0n", 8),
+        ];
+        for (pointer, value) in expected.iter() {
+            assert_eq!(json.pointer(pointer), Ok(&Json::from(*value)));
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut json = parse_complete(r#"{"a": [1, {"b": null}], "c": "d"}"#).unwrap();
+        let cases = [
+            ("/x/y", PointerError::MissingKey(ptr("/x"))),
+            ("/a/2", PointerError::IndexOutOfBounds(ptr("/a/2"))),
+            ("/a/-", PointerError::IndexOutOfBounds(ptr("/a/-"))),
+            (
+                "/a/99999999999999999999999",
+                PointerError::IndexOutOfBounds(ptr("/a/99999999999999999999999")),
+            ),
+            ("/a/01", PointerError::InvalidIndex(ptr("/a/01"))),
+            ("/a/+1", PointerError::InvalidIndex(ptr("/a/+1"))),
+            ("/a/", PointerError::InvalidIndex(ptr("/a/"))),
+            ("/c/0", PointerError::NotAContainer(ptr("/c/0"))),
+            ("/a/1/b/c", PointerError::NotAContainer(ptr("/a/1/b/c"))),
+        ];
+        for (pointer, err) in cases.iter() {
+            assert_eq!(json.pointer(pointer).as_ref(), Err(err));
+            assert_eq!(json.pointer_mut(pointer).map(|_| ()), Err(err.clone()));
+            assert_eq!(json.pointer_remove(pointer).as_ref(), Err(err));
+        }
+        assert_eq!(
+            PointerError::MissingKey(ptr("/x")).to_string(),
+            "missing key at \"/x\""
+        );
+        assert_eq!(
+            PointerError::InvalidSyntax("x".into()).to_string(),
+            "invalid JSON pointer \"x\""
+        );
+    }
+
+    #[test]
+    fn test_mutation() {
+        let mut json = parse_complete(r#"{"a": [1, 2], "b": {}}"#).unwrap();
+        *json.pointer_mut("/a/0").unwrap() = "one".into();
+        assert_eq!(json.pointer_insert("/a/2", 3), Ok(None));
+        assert_eq!(json.pointer_insert("/a/0", 0), Ok(None));
+        assert_eq!(json.pointer_insert("/a/-", 4), Ok(None));
+        assert_eq!(
+            json.pointer_insert("/a/6", 6),
+            Err(PointerError::IndexOutOfBounds(ptr("/a/6")))
+        );
+        assert_eq!(json.pointer_insert("/b/c", true), Ok(None));
+        assert_eq!(json.pointer_insert("/b/c", false), Ok(Some(true.into())));
+        assert_eq!(
+            json.pointer_insert("/b/c/d", 1),
+            Err(PointerError::NotAContainer(ptr("/b/c/d")))
+        );
+        assert_eq!(
+            json.pointer_insert("/x/y", 1),
+            Err(PointerError::MissingKey(ptr("/x")))
+        );
+        assert_eq!(
+            json.to_string(),
+            r#"{"a": [0, "one", 2, 3, 4], "b": {"c": false}}"#
+        );
+
+        assert_eq!(json.pointer_remove("/a/1"), Ok("one".into()));
+        assert_eq!(json.pointer_remove("/b/c"), Ok(false.into()));
+        assert_eq!(
+            json.pointer_remove("/b/c"),
+            Err(PointerError::MissingKey(ptr("/b/c")))
+        );
+        assert_eq!(json.to_string(), r#"{"a": [0, 2, 3, 4], "b": {}}"#);
+
+        let old = json.clone();
+        assert_eq!(json.pointer_remove(""), Ok(old));
+        assert!(json.is_null());
+        assert_eq!(json.pointer_insert("", vec![1]), Ok(Some(Json(None))));
+        assert_eq!(json, Json::from(vec![1]));
+    }
+}
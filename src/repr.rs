@@ -1,3 +1,5 @@
+use crate::map::Map;
+use crate::number::Number;
 use crate::utils::{escape, PadAdapter};
 
 use derive_more::From as DmFrom;
@@ -5,9 +7,11 @@ use itertools::Itertools;
 use shrinkwraprs::Shrinkwrap;
 use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::convert::TryFrom;
 use std::fmt::Display;
 use std::fmt::{Error, Formatter, Write};
 use std::iter::FromIterator;
+use std::ops::Index;
 
 /// A struct representing a JSON value.
 ///
@@ -42,27 +46,385 @@ use std::iter::FromIterator;
 #[derive(Shrinkwrap, PartialEq, PartialOrd, Clone, DmFrom, Debug)]
 pub struct Json<'a>(pub(crate) Option<JsonValue<'a>>);
 
+/// The `null` returned by the `Index` impls of `Json` when there is no such member or element.
+static NULL: Json<'static> = Json(None);
+
 /// An enum representing a non-null JSON value.
 #[derive(PartialEq, PartialOrd, DmFrom, Clone, Debug)]
 pub enum JsonValue<'a> {
     /// An integer, that is, any JSON number that does not contain a decimal point or exponent
     /// and can fit into an `i64`.
+    #[from(ignore)]
     Int(i64),
+    /// An integer that is too large to fit into an `i64`, but can fit into a `u64`.
+    ///
+    /// Conversions from Rust integers only produce this variant for values above `i64::MAX`.
+    #[from(ignore)]
+    UInt(u64),
     /// A JSON number that either contain a decimal point or exponent,
-    /// or too large to fit into an `i64`
+    /// or too large to fit into an `i64` or `u64`
+    ///
+    /// The `Infinity` and `NaN` of JSON5 are displayed as `null`, as JSON has no such numbers.
+    /// Only [`Json::json5`](struct.Json.html#method.json5) keeps them.
     Float(f64),
+    /// A JSON number kept as its source text, produced when parsing with
+    /// [`ParserOptions::lossless_numbers`](struct.ParserOptions.html#method.lossless_numbers).
+    Number(Number<'a>),
     /// A JSON string.
     String(Cow<'a, str>),
     /// A JSON boolean value.
     Bool(bool),
-    /// A JSON object
-    #[from(ignore)]
-    Object(BTreeMap<Cow<'a, str>, Json<'a>>),
+    /// A JSON object, keeping its members in insertion order
+    Object(Map<'a>),
     /// A JSON array
     #[from(ignore)]
     Array(Vec<Json<'a>>),
 }
 
+impl<'a> Json<'a> {
+    /// Returns `true` if the value is `null`.
+    pub fn is_null(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Returns a reference to the underlying value, or `None` for `null`.
+    pub fn as_value(&self) -> Option<&JsonValue<'a>> {
+        self.0.as_ref()
+    }
+
+    /// Returns a mutable reference to the underlying value, or `None` for `null`.
+    pub fn as_value_mut(&mut self) -> Option<&mut JsonValue<'a>> {
+        self.0.as_mut()
+    }
+
+    /// Returns the value of the member with the given key, if this is an object that has one.
+    ///
+    /// # Example
+    /// ```rust
+    /// use nom_json_parser::parse_complete;
+    ///
+    /// let json = parse_complete(r#"{"phone": {"work": "123-456-7890"}, "tags": [true]}"#).unwrap();
+    /// let work = json.get("phone").and_then(|phone| phone.get("work"));
+    /// assert_eq!(work.and_then(|work| work.as_str()), Some("123-456-7890"));
+    /// assert_eq!(json["tags"][0].as_bool(), Some(true));
+    /// assert!(json["missing"][3].is_null());
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&Json<'a>> {
+        self.as_value()?.get(key)
+    }
+
+    /// Returns a mutable reference to the value of the member with the given key, if this is an
+    /// object that has one.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Json<'a>> {
+        self.as_value_mut()?.get_mut(key)
+    }
+
+    /// Returns the element at the given index, if this is an array that is long enough.
+    pub fn get_index(&self, index: usize) -> Option<&Json<'a>> {
+        self.as_value()?.get_index(index)
+    }
+
+    /// Returns a mutable reference to the element at the given index, if this is an array that is
+    /// long enough.
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut Json<'a>> {
+        self.as_value_mut()?.get_index_mut(index)
+    }
+
+    /// Returns the string, if this is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_value()?.as_str()
+    }
+
+    /// Returns a mutable reference to the string, if this is a string.
+    pub fn as_str_mut(&mut self) -> Option<&mut Cow<'a, str>> {
+        self.as_value_mut()?.as_str_mut()
+    }
+
+    /// Returns the number as an `i64`, if this is an integer in range. See
+    /// [`JsonValue::as_i64`](enum.JsonValue.html#method.as_i64).
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_value()?.as_i64()
+    }
+
+    /// Returns the number as a `u64`, if this is a non-negative integer in range. See
+    /// [`JsonValue::as_u64`](enum.JsonValue.html#method.as_u64).
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_value()?.as_u64()
+    }
+
+    /// Returns the number as an `f64`, if this is a number. See
+    /// [`JsonValue::as_f64`](enum.JsonValue.html#method.as_f64).
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_value()?.as_f64()
+    }
+
+    /// Returns the boolean, if this is a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        self.as_value()?.as_bool()
+    }
+
+    /// Returns the elements, if this is an array.
+    pub fn as_array(&self) -> Option<&Vec<Json<'a>>> {
+        self.as_value()?.as_array()
+    }
+
+    /// Returns a mutable reference to the elements, if this is an array.
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Json<'a>>> {
+        self.as_value_mut()?.as_array_mut()
+    }
+
+    /// Returns the members, if this is an object.
+    pub fn as_object(&self) -> Option<&Map<'a>> {
+        self.as_value()?.as_object()
+    }
+
+    /// Returns a mutable reference to the members, if this is an object.
+    pub fn as_object_mut(&mut self) -> Option<&mut Map<'a>> {
+        self.as_value_mut()?.as_object_mut()
+    }
+
+    /// Converts the value into one that owns all of its strings, so that it no longer borrows
+    /// from the parsed input.
+    ///
+    /// Strings that are already owned are moved rather than copied. `Json<'static>` can be kept
+    /// after the input is dropped or sent to another thread.
+    ///
+    /// `Json` cannot implement `ToOwned` with an owned type other than itself, since it is
+    /// already `Clone`. [`to_owned_json`](#method.to_owned_json) and
+    /// [`borrow`](#method.borrow) convert in both directions instead.
+    ///
+    /// # Example
+    /// ```rust
+    /// use nom_json_parser::{parse_complete, Json};
+    ///
+    /// let input = String::from(r#"{"name": "Alice", "tags": ["a", "b"]}"#);
+    /// let owned: Json<'static> = parse_complete(&input).unwrap().into_owned();
+    /// drop(input);
+    ///
+    /// let handle = std::thread::spawn(move || owned["tags"][1].as_str().map(String::from));
+    /// assert_eq!(handle.join().unwrap().as_deref(), Some("b"));
+    /// ```
+    pub fn into_owned(self) -> Json<'static> {
+        Json(self.0.map(JsonValue::into_owned))
+    }
+
+    /// Returns a copy of the value that owns all of its strings. See
+    /// [`into_owned`](#method.into_owned).
+    pub fn to_owned_json(&self) -> Json<'static> {
+        self.clone().into_owned()
+    }
+
+    /// Returns a value that borrows all of its strings from `self`.
+    ///
+    /// This is the inverse of [`into_owned`](#method.into_owned): arrays and objects are
+    /// rebuilt, but no string is copied. It is useful for passing an owned value to code that
+    /// works with borrowed `Json`, or for mutating a view without touching the original.
+    ///
+    /// # Example
+    /// ```rust
+    /// use nom_json_parser::{json, Json};
+    ///
+    /// let owned: Json<'static> = json!({"name": String::from("Alice")});
+    /// let mut view = owned.borrow();
+    /// view.as_object_mut().unwrap().insert("age", 20);
+    /// assert_eq!(view.to_string(), r#"{"name": "Alice", "age": 20}"#);
+    /// assert_eq!(owned.to_string(), r#"{"name": "Alice"}"#);
+    /// ```
+    pub fn borrow(&self) -> Json<'_> {
+        Json(self.0.as_ref().map(JsonValue::borrow))
+    }
+}
+
+impl<'a> JsonValue<'a> {
+    /// Returns the value of the member with the given key, if this is an object that has one.
+    pub fn get(&self, key: &str) -> Option<&Json<'a>> {
+        self.as_object()?.get(key)
+    }
+
+    /// Returns a mutable reference to the value of the member with the given key, if this is an
+    /// object that has one.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Json<'a>> {
+        self.as_object_mut()?.get_mut(key)
+    }
+
+    /// Returns the element at the given index, if this is an array that is long enough.
+    pub fn get_index(&self, index: usize) -> Option<&Json<'a>> {
+        self.as_array()?.get(index)
+    }
+
+    /// Returns a mutable reference to the element at the given index, if this is an array that is
+    /// long enough.
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut Json<'a>> {
+        self.as_array_mut()?.get_mut(index)
+    }
+
+    /// Returns the string, if this is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the string, if this is a string.
+    pub fn as_str_mut(&mut self) -> Option<&mut Cow<'a, str>> {
+        match self {
+            JsonValue::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Returns the number as an `i64`, if this is an integer in range.
+    ///
+    /// Floats are never converted, even if they have no fractional part.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValue::Int(int) => Some(*int),
+            JsonValue::UInt(uint) => i64::try_from(*uint).ok(),
+            JsonValue::Number(number) => number.as_i64(),
+            _ => None,
+        }
+    }
+
+    /// Returns the number as a `u64`, if this is a non-negative integer in range.
+    ///
+    /// Floats are never converted, even if they have no fractional part.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::Int(int) => u64::try_from(*int).ok(),
+            JsonValue::UInt(uint) => Some(*uint),
+            JsonValue::Number(number) => number.as_u64(),
+            _ => None,
+        }
+    }
+
+    /// Returns the number as an `f64`, if this is a number. Integers are rounded to the nearest
+    /// representable value.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Int(int) => Some(*int as f64),
+            JsonValue::UInt(uint) => Some(*uint as f64),
+            JsonValue::Float(float) => Some(*float),
+            JsonValue::Number(number) => number.as_f64(),
+            _ => None,
+        }
+    }
+
+    /// Returns the boolean, if this is a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(boolean) => Some(*boolean),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements, if this is an array.
+    pub fn as_array(&self) -> Option<&Vec<Json<'a>>> {
+        match self {
+            JsonValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the elements, if this is an array.
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Json<'a>>> {
+        match self {
+            JsonValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    /// Returns the members, if this is an object.
+    pub fn as_object(&self) -> Option<&Map<'a>> {
+        match self {
+            JsonValue::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the members, if this is an object.
+    pub fn as_object_mut(&mut self) -> Option<&mut Map<'a>> {
+        match self {
+            JsonValue::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    /// Converts the value into one that owns all of its strings. See
+    /// [`Json::into_owned`](struct.Json.html#method.into_owned).
+    pub fn into_owned(self) -> JsonValue<'static> {
+        match self {
+            JsonValue::Int(int) => JsonValue::Int(int),
+            JsonValue::UInt(uint) => JsonValue::UInt(uint),
+            JsonValue::Float(float) => JsonValue::Float(float),
+            JsonValue::Number(number) => JsonValue::Number(number.into_owned()),
+            JsonValue::String(string) => JsonValue::String(Cow::Owned(string.into_owned())),
+            JsonValue::Bool(boolean) => JsonValue::Bool(boolean),
+            JsonValue::Object(obj) => JsonValue::Object(obj.into_owned()),
+            JsonValue::Array(arr) => {
+                JsonValue::Array(arr.into_iter().map(Json::into_owned).collect())
+            }
+        }
+    }
+
+    /// Returns a copy of the value that owns all of its strings. See
+    /// [`Json::into_owned`](struct.Json.html#method.into_owned).
+    pub fn to_owned_json(&self) -> JsonValue<'static> {
+        self.clone().into_owned()
+    }
+
+    /// Returns a value that borrows all of its strings from `self`. See
+    /// [`Json::borrow`](struct.Json.html#method.borrow).
+    pub fn borrow(&self) -> JsonValue<'_> {
+        match self {
+            JsonValue::Int(int) => JsonValue::Int(*int),
+            JsonValue::UInt(uint) => JsonValue::UInt(*uint),
+            JsonValue::Float(float) => JsonValue::Float(*float),
+            JsonValue::Number(number) => JsonValue::Number(number.borrow()),
+            JsonValue::String(string) => JsonValue::String(Cow::Borrowed(string)),
+            JsonValue::Bool(boolean) => JsonValue::Bool(*boolean),
+            JsonValue::Object(obj) => JsonValue::Object(obj.borrow()),
+            JsonValue::Array(arr) => JsonValue::Array(arr.iter().map(Json::borrow).collect()),
+        }
+    }
+}
+
+/// Looks up an object member by key, returning `null` if this is not an object or the key is
+/// missing.
+impl<'a> Index<&str> for Json<'a> {
+    type Output = Json<'a>;
+
+    fn index(&self, key: &str) -> &Self::Output {
+        self.get(key).unwrap_or(&NULL)
+    }
+}
+
+/// Looks up an array element, returning `null` if this is not an array or the index is out of
+/// bounds.
+impl<'a> Index<usize> for Json<'a> {
+    type Output = Json<'a>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get_index(index).unwrap_or(&NULL)
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for JsonValue<'_> {
+                fn from(arg: $ty) -> Self {
+                    i64::try_from(arg)
+                        .map(JsonValue::Int)
+                        .unwrap_or(JsonValue::UInt(arg as u64))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
 impl<'a> From<&'a str> for JsonValue<'a> {
     fn from(arg: &'a str) -> Self {
         JsonValue::String(arg.into())
@@ -83,11 +445,7 @@ impl<'a, V: Into<Json<'a>>> FromIterator<V> for JsonValue<'a> {
 
 impl<'a, K: Into<Cow<'a, str>>, V: Into<Json<'a>>> FromIterator<(K, V)> for JsonValue<'a> {
     fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
-        JsonValue::Object(
-            iter.into_iter()
-                .map(|(k, v)| (k.into(), v.into()))
-                .collect(),
-        )
+        JsonValue::Object(iter.into_iter().collect())
     }
 }
 
@@ -129,6 +487,12 @@ impl Display for JsonValue<'_> {
             JsonValue::Int(int) => {
                 f.write_str(&int.to_string())?;
             }
+            JsonValue::UInt(uint) => {
+                f.write_str(&uint.to_string())?;
+            }
+            JsonValue::Float(float) if !float.is_finite() => {
+                f.write_str("null")?;
+            }
             JsonValue::Float(float) => {
                 if float.fract() == 0.0 {
                     f.write_fmt(format_args!("{}.0", float))?;
@@ -136,6 +500,9 @@ impl Display for JsonValue<'_> {
                     f.write_fmt(format_args!("{}", float))?;
                 };
             }
+            JsonValue::Number(number) => {
+                f.write_str(number.as_str())?;
+            }
             JsonValue::String(string) => {
                 f.write_str("\"")?;
                 f.write_str(&escape(string))?;
@@ -182,9 +549,52 @@ impl Display for JsonValue<'_> {
     }
 }
 
+/// Compares two values as required by the `test` operation and JSONPath filters: numbers by
+/// value, and objects regardless of member order.
+pub(crate) fn equal(lhs: &Json<'_>, rhs: &Json<'_>) -> bool {
+    match (lhs.as_value(), rhs.as_value()) {
+        (Some(JsonValue::Array(lhs)), Some(JsonValue::Array(rhs))) => {
+            lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(lhs, rhs)| equal(lhs, rhs))
+        }
+        (Some(JsonValue::Object(lhs)), Some(JsonValue::Object(rhs))) => {
+            lhs.len() == rhs.len()
+                && lhs
+                    .iter()
+                    .all(|(key, lhs)| rhs.get(key).is_some_and(|rhs| equal(lhs, rhs)))
+        }
+        (Some(lhs), Some(rhs)) if is_number(lhs) && is_number(rhs) => {
+            match (as_integer(lhs), as_integer(rhs)) {
+                (Some(lhs), Some(rhs)) => lhs == rhs,
+                _ => lhs.as_f64() == rhs.as_f64(),
+            }
+        }
+        _ => lhs == rhs,
+    }
+}
+
+/// Returns whether the value is a number of any representation.
+pub(crate) fn is_number(value: &JsonValue<'_>) -> bool {
+    matches!(
+        value,
+        JsonValue::Int(_) | JsonValue::UInt(_) | JsonValue::Float(_) | JsonValue::Number(_)
+    )
+}
+
+/// Returns the value of an integer of any representation.
+pub(crate) fn as_integer(value: &JsonValue<'_>) -> Option<i128> {
+    match value {
+        JsonValue::Int(int) => Some(i128::from(*int)),
+        JsonValue::UInt(uint) => Some(i128::from(*uint)),
+        JsonValue::Number(number) => number.as_i128(),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::options::ParserOptions;
+    use crate::parser::Parser;
     use maplit::btreemap;
 
     #[test]
@@ -215,6 +625,103 @@ mod test {
         println!("{:#}", nested_json);
     }
 
+    #[test]
+    fn test_from_int() {
+        assert_eq!(JsonValue::from(-1i8), JsonValue::Int(-1));
+        assert_eq!(JsonValue::from(42u64), JsonValue::Int(42));
+        assert_eq!(
+            JsonValue::from(i64::MAX as u64 + 1),
+            JsonValue::UInt(i64::MAX as u64 + 1)
+        );
+        assert_eq!(Json::from(u64::MAX).to_string(), "18446744073709551615");
+        assert!(JsonValue::Int(i64::MAX) < JsonValue::UInt(i64::MAX as u64 + 1));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut json: Json = btreemap! {
+            "name" => Json::from("Alice"),
+            "age" => 20.into(),
+            "height" => 1.65.into(),
+            "id" => u64::MAX.into(),
+            "admin" => false.into(),
+            "tags" => vec!["a", "b"].into(),
+            "spouse" => Json::from(None),
+        }
+        .into();
+        assert_eq!(json["name"].as_str(), Some("Alice"));
+        assert_eq!(json["age"].as_i64(), Some(20));
+        assert_eq!(json["age"].as_f64(), Some(20.0));
+        assert_eq!(json["height"].as_i64(), None);
+        assert_eq!(json["id"].as_u64(), Some(u64::MAX));
+        assert_eq!(json["id"].as_i64(), None);
+        assert_eq!(json["admin"].as_bool(), Some(false));
+        assert_eq!(json["tags"].as_array().map(Vec::len), Some(2));
+        assert_eq!(json["tags"][1].as_str(), Some("b"));
+        assert_eq!(json.as_object().map(Map::len), Some(7));
+        assert!(json["spouse"].is_null());
+        assert!(json["missing"].is_null());
+        assert!(json["tags"][2].is_null());
+        assert!(json["name"]["first"].is_null());
+        assert_eq!(json.get("spouse"), Some(&Json::from(None)));
+        assert_eq!(json.get("missing"), None);
+        assert_eq!(json.get_index(0), None);
+        assert_eq!(json["age"].as_str(), None);
+
+        json.get_mut("tags")
+            .and_then(Json::as_array_mut)
+            .unwrap()
+            .push("c".into());
+        *json
+            .get_mut("tags")
+            .and_then(|tags| tags.get_index_mut(0))
+            .unwrap() = 1.into();
+        *json.get_mut("name").and_then(Json::as_str_mut).unwrap() += " Brown";
+        json.as_object_mut().unwrap().remove("spouse");
+        assert_eq!(
+            json["tags"],
+            Json::from(vec![1.into(), "b".into(), Json::from("c")])
+        );
+        assert_eq!(json["name"].as_str(), Some("Alice Brown"));
+        assert_eq!(json.get("spouse"), None);
+    }
+
+    #[test]
+    fn test_ownership() {
+        let input = String::from(r#"{"a": ["b", 1.50], "c\n": {"d": null}}"#);
+        let parser = Parser::with_options(ParserOptions::new().lossless_numbers(true));
+        let borrowed = parser.parse_complete(&input).unwrap();
+        let owned = borrowed.to_owned_json();
+        assert_eq!(owned, borrowed);
+        assert_eq!(owned.borrow(), borrowed);
+
+        let owned = borrowed.into_owned();
+        drop(input);
+        assert_eq!(
+            owned.to_string(),
+            r#"{"a": ["b", 1.50], "c\n": {"d": null}}"#
+        );
+        let is_owned =
+            |json: &Json| matches!(json.as_value(), Some(JsonValue::String(Cow::Owned(_))));
+        assert!(is_owned(&owned["a"][0]));
+        assert!(!is_owned(&owned.borrow()["a"][0]));
+    }
+
+    #[test]
+    fn test_display_non_finite() {
+        let parser = ParserOptions::new().json5(true).build();
+        let json = parser
+            .parse_complete("[Infinity, -Infinity, NaN, 1.5]")
+            .unwrap();
+        assert_eq!(json.to_string(), "[null, null, null, 1.5]");
+        assert_eq!(
+            format!("{:#}", json),
+            "[\n    null,\n    null,\n    null,\n    1.5\n]"
+        );
+        assert!(Parser::new().parse_complete(&json.to_string()).is_ok());
+        assert_eq!(json.json5().to_string(), "[Infinity, -Infinity, NaN, 1.5]");
+    }
+
     #[test]
     fn test_escape() {
         assert_eq!(&escape(r#""Hello\World""#), r#"\"Hello\\World\""#);
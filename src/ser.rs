@@ -0,0 +1,561 @@
+use crate::de::SerdeError;
+use crate::map::Map;
+use crate::number::Number;
+use crate::repr::{Json, JsonValue};
+
+use serde::ser::{self, Error as _, Serialize, SerializeMap as _, SerializeSeq as _};
+use std::convert::TryFrom;
+use std::io;
+
+/// Serializes `value` into a [`Json`](struct.Json.html) tree that owns all of its strings.
+///
+/// Non-finite floats become `null`, and byte arrays become arrays of numbers. Map keys must
+/// serialize to strings or integers, and integer keys are converted to strings.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::{json, to_json};
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// enum Shape {
+///     Circle { radius: f64 },
+///     Empty,
+/// }
+///
+/// let shapes = vec![Shape::Circle { radius: 1.5 }, Shape::Empty];
+/// assert_eq!(
+///     to_json(&shapes).unwrap(),
+///     json!([{"Circle": {"radius": 1.5}}, "Empty"])
+/// );
+/// ```
+pub fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<Json<'static>, SerdeError> {
+    value.serialize(Serializer)
+}
+
+/// Serializes `value` as JSON text.
+///
+/// The output is the same as printing the result of [`to_json`](fn.to_json.html) with `{}`, and
+/// strings are escaped the same way.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::to_string;
+/// use std::collections::BTreeMap;
+///
+/// let mut map = BTreeMap::new();
+/// map.insert(1, vec!["a\"b", "c"]);
+/// assert_eq!(to_string(&map).unwrap(), r#"{"1": ["a\"b", "c"]}"#);
+/// ```
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, SerdeError> {
+    Ok(to_json(value)?.to_string())
+}
+
+/// Serializes `value` as JSON text into `writer`. See [`to_string`](fn.to_string.html).
+pub fn to_writer<W: io::Write, T: Serialize + ?Sized>(
+    mut writer: W,
+    value: &T,
+) -> Result<(), SerdeError> {
+    let json = to_json(value)?;
+    write!(writer, "{}", json).map_err(SerdeError::io)
+}
+
+impl Serialize for Json<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.as_value() {
+            Some(value) => value.serialize(serializer),
+            None => serializer.serialize_unit(),
+        }
+    }
+}
+
+/// Numbers kept as text are serialized as the first of `i64`, `u64`, `i128` and `f64` that can
+/// represent them.
+impl Serialize for JsonValue<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            JsonValue::Int(int) => serializer.serialize_i64(*int),
+            JsonValue::UInt(uint) => serializer.serialize_u64(*uint),
+            JsonValue::Float(float) => serializer.serialize_f64(*float),
+            JsonValue::Number(number) => number.serialize(serializer),
+            JsonValue::String(string) => serializer.serialize_str(string),
+            JsonValue::Bool(boolean) => serializer.serialize_bool(*boolean),
+            JsonValue::Object(obj) => obj.serialize(serializer),
+            JsonValue::Array(arr) => {
+                let mut seq = serializer.serialize_seq(Some(arr.len()))?;
+                for elem in arr {
+                    seq.serialize_element(elem)?;
+                }
+                seq.end()
+            }
+        }
+    }
+}
+
+impl Serialize for Number<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(int) = self.as_i64() {
+            serializer.serialize_i64(int)
+        } else if let Some(uint) = self.as_u64() {
+            serializer.serialize_u64(uint)
+        } else if let Some(int) = self.as_i128() {
+            serializer.serialize_i128(int)
+        } else if let Some(float) = self.as_f64() {
+            serializer.serialize_f64(float)
+        } else {
+            Err(S::Error::custom(format_args!(
+                "number {} is out of range",
+                self
+            )))
+        }
+    }
+}
+
+impl Serialize for Map<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl ser::Error for SerdeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        <SerdeError as serde::de::Error>::custom(msg)
+    }
+}
+
+/// Serializes values into `Json<'static>`.
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Json<'static>;
+    type Error = SerdeError;
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeVariant<SerializeArray>;
+    type SerializeMap = SerializeObject;
+    type SerializeStruct = SerializeObject;
+    type SerializeStructVariant = SerializeVariant<SerializeObject>;
+
+    fn serialize_bool(self, v: bool) -> Result<Json<'static>, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Json<'static>, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Json<'static>, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Json<'static>, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Json<'static>, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Json<'static>, SerdeError> {
+        match i64::try_from(v) {
+            Ok(int) => Ok(int.into()),
+            Err(_) => Ok(Number::new(v.to_string()).unwrap().into()),
+        }
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Json<'static>, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Json<'static>, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Json<'static>, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Json<'static>, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Json<'static>, SerdeError> {
+        match u64::try_from(v) {
+            Ok(uint) => Ok(uint.into()),
+            Err(_) => Ok(Number::new(v.to_string()).unwrap().into()),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Json<'static>, SerdeError> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Json<'static>, SerdeError> {
+        if v.is_finite() {
+            Ok(v.into())
+        } else {
+            Ok(Json(None))
+        }
+    }
+
+    fn serialize_char(self, v: char) -> Result<Json<'static>, SerdeError> {
+        Ok(v.to_string().into())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Json<'static>, SerdeError> {
+        Ok(v.to_string().into())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Json<'static>, SerdeError> {
+        Ok(v.to_vec().into())
+    }
+
+    fn serialize_none(self) -> Result<Json<'static>, SerdeError> {
+        Ok(Json(None))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Json<'static>, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Json<'static>, SerdeError> {
+        Ok(Json(None))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Json<'static>, SerdeError> {
+        Ok(Json(None))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Json<'static>, SerdeError> {
+        Ok(variant.into())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Json<'static>, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Json<'static>, SerdeError> {
+        Ok(wrap_variant(variant, to_json(value)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray, SerdeError> {
+        Ok(SerializeArray(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeArray>, SerdeError> {
+        Ok(SerializeVariant(variant, self.serialize_seq(Some(len))?))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeObject, SerdeError> {
+        Ok(SerializeObject {
+            obj: Map::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeObject, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeObject>, SerdeError> {
+        Ok(SerializeVariant(variant, self.serialize_map(Some(len))?))
+    }
+}
+
+struct SerializeArray(Vec<Json<'static>>);
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = Json<'static>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.0.push(to_json(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Json<'static>, SerdeError> {
+        Ok(JsonValue::Array(self.0).into())
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = Json<'static>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Json<'static>, SerdeError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = Json<'static>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Json<'static>, SerdeError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct SerializeObject {
+    obj: Map<'static>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeObject {
+    type Ok = Json<'static>;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        let key = to_json(key)?;
+        let key = match key.as_value() {
+            Some(JsonValue::String(string)) => string.to_string(),
+            Some(JsonValue::Int(_)) | Some(JsonValue::UInt(_)) | Some(JsonValue::Number(_)) => {
+                key.to_string()
+            }
+            _ => return Err(SerdeError::custom("key must be a string")),
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| SerdeError::custom("serialize_value called before serialize_key"))?;
+        self.obj.insert(key, to_json(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Json<'static>, SerdeError> {
+        Ok(self.obj.into())
+    }
+}
+
+impl ser::SerializeStruct for SerializeObject {
+    type Ok = Json<'static>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.obj.insert(key, to_json(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Json<'static>, SerdeError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Wraps the content of an enum variant in an object with the variant name as the only key.
+fn wrap_variant(variant: &'static str, content: Json<'static>) -> Json<'static> {
+    let mut obj = Map::new();
+    obj.insert(variant, content);
+    obj.into()
+}
+
+/// Serializes the content of a tuple or struct variant, then wraps it with `wrap_variant`.
+struct SerializeVariant<S>(&'static str, S);
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeArray> {
+    type Ok = Json<'static>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.1.serialize_element(value)
+    }
+
+    fn end(self) -> Result<Json<'static>, SerdeError> {
+        Ok(wrap_variant(self.0, self.1.end()?))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeObject> {
+    type Ok = Json<'static>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        ser::SerializeStruct::serialize_field(&mut self.1, key, value)
+    }
+
+    fn end(self) -> Result<Json<'static>, SerdeError> {
+        Ok(wrap_variant(self.0, self.1.end()?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::de::{from_json, from_str, SerdeErrorKind};
+    use crate::parser::parse_complete;
+    use serde_derive::{Deserialize, Serialize};
+    use std::borrow::Cow;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Op {
+        Noop,
+        Push(i128),
+        Move(u8, u8),
+        Rename { from: String, to: String },
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Doc<'a> {
+        title: String,
+        ops: Vec<Op>,
+        weights: BTreeMap<u32, f32>,
+        #[serde(borrow)]
+        raw: Json<'a>,
+        missing: Option<()>,
+    }
+
+    #[test]
+    fn test_to_json() {
+        let doc = Doc {
+            title: "tab\there".into(),
+            ops: vec![
+                Op::Noop,
+                Op::Push(-170141183460469231731687303715884105728),
+                Op::Move(1, 2),
+                Op::Rename {
+                    from: "a".into(),
+                    to: "b".into(),
+                },
+            ],
+            weights: vec![(2, 0.5), (10, f32::NAN)].into_iter().collect(),
+            raw: json!({"any": [1, "thing"]}),
+            missing: None,
+        };
+        let text = r#"{"title": "tab\there", "ops": ["Noop", {"Push": -170141183460469231731687303715884105728}, {"Move": [1, 2]}, {"Rename": {"from": "a", "to": "b"}}], "weights": {"2": 0.5, "10": null}, "raw": {"any": [1, "thing"]}, "missing": null}"#;
+        let json = to_json(&doc).unwrap();
+        assert_eq!(json.to_string(), text);
+        assert_eq!(to_string(&doc).unwrap(), text);
+
+        let mut out = Vec::new();
+        to_writer(&mut out, &doc).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), text);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = r#"{"title": "t", "ops": [{"Push": 7}], "weights": {"2": 0.5, "10": 0.25}, "raw": ["x\n", {"y": 1.5}], "missing": null}"#;
+        let doc: Doc = from_str(text).unwrap();
+        assert_eq!(doc.raw, json!(["x\n", {"y": 1.5}]));
+        assert_eq!(to_string(&doc).unwrap(), text);
+
+        let json = parse_complete(text).unwrap();
+        assert_eq!(from_json::<Doc>(&json).unwrap(), doc);
+        assert_eq!(to_json(&json).unwrap(), json);
+        assert_eq!(from_str::<Json>(text).unwrap(), json);
+    }
+
+    #[test]
+    fn test_borrowed_json() {
+        let json: Json = from_str(r#"{"plain": "a", "escaped": "\u00e9"}"#).unwrap();
+        let is_borrowed =
+            |json: &Json| matches!(json.as_value(), Some(JsonValue::String(Cow::Borrowed(_))));
+        assert!(is_borrowed(&json["plain"]));
+        assert!(!is_borrowed(&json["escaped"]));
+        assert_eq!(json["escaped"].as_str(), Some("é"));
+
+        let err = from_str::<JsonValue>("null").unwrap_err();
+        assert_eq!(err.kind(), SerdeErrorKind::Data);
+        assert!(from_str::<JsonValue>("[null]").is_ok());
+    }
+
+    #[test]
+    fn test_lossless_numbers() {
+        let parser = crate::ParserOptions::new().lossless_numbers(true).build();
+        let json = parser
+            .parse_complete("[1.50, 18446744073709551616]")
+            .unwrap();
+        assert_eq!(to_string(&json).unwrap(), "[1.5, 18446744073709551616]");
+        let huge = parser.parse_complete("1e400").unwrap();
+        assert_eq!(to_json(&huge).unwrap_err().kind(), SerdeErrorKind::Data);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut map = BTreeMap::new();
+        map.insert((1, 2), 3);
+        let err = to_json(&map).unwrap_err();
+        assert_eq!(err.kind(), SerdeErrorKind::Data);
+        assert_eq!(err.to_string(), "key must be a string");
+
+        struct Failing;
+        impl io::Write for Failing {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let err = to_writer(Failing, &[1, 2]).unwrap_err();
+        assert_eq!(err.kind(), SerdeErrorKind::Io(io::ErrorKind::BrokenPipe));
+    }
+}
@@ -0,0 +1,333 @@
+use crate::error::Location;
+use crate::map::Map;
+use crate::options::DuplicateKeys;
+use crate::parser::{Collect, Lexeme, Member, Members, Syntax};
+use crate::pointer::JsonPointer;
+use crate::repr::Json;
+
+use indexmap::IndexMap;
+use nom::Offset;
+use std::mem;
+use std::ops::Range;
+
+/// The location of a value or key in the source text, from its first byte to the byte after it.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Span {
+    /// The location of the first character.
+    pub start: Location,
+    /// The location right after the last character.
+    pub end: Location,
+}
+
+impl Span {
+    /// Returns the byte range of the span in the source.
+    pub fn range(&self) -> Range<usize> {
+        self.start.offset..self.end.offset
+    }
+}
+
+/// The spans of the values and object keys of a document, keyed by JSON Pointer, created by
+/// [`parse_with_spans`](fn.parse_with_spans.html) or
+/// [`Parser::parse_with_spans`](struct.Parser.html#method.parse_with_spans).
+///
+/// The pointers refer to the parsed `Json`, so repeated keys follow the `duplicate_keys` option.
+/// With `DuplicateKeys::Collect`, each occurrence of a repeated key has its value and key span at
+/// its index in the collected array, which has no span of its own.
+///
+/// # Example
+/// ```rust
+/// use nom_json_parser::parse_with_spans;
+///
+/// let input = "{\n  \"name\": \"x\",\n  \"tags\": [1, true]\n}";
+/// let (json, spans) = parse_with_spans(input).unwrap();
+/// assert_eq!(json.pointer("/tags/1").unwrap().as_bool(), Some(true));
+///
+/// let span = spans.value("/tags/1").unwrap();
+/// assert_eq!(&input[span.range()], "true");
+/// assert_eq!((span.start.line, span.start.column), (3, 15));
+///
+/// let key = spans.key("/name").unwrap();
+/// assert_eq!(&input[key.range()], "\"name\"");
+/// assert_eq!(spans.value("").unwrap().range(), 0..input.len());
+/// ```
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+pub struct Spans {
+    values: IndexMap<JsonPointer, Span>,
+    keys: IndexMap<JsonPointer, Span>,
+}
+
+impl Spans {
+    /// Returns the span of the value at the given JSON Pointer, or `None` if there is no such
+    /// value or the pointer is invalid.
+    pub fn value(&self, pointer: &str) -> Option<Span> {
+        self.values
+            .get(&pointer.parse::<JsonPointer>().ok()?)
+            .copied()
+    }
+
+    /// Returns the span of the key of the object member at the given JSON Pointer, or `None` if
+    /// the pointer does not refer to an object member or is invalid.
+    pub fn key(&self, pointer: &str) -> Option<Span> {
+        self.keys
+            .get(&pointer.parse::<JsonPointer>().ok()?)
+            .copied()
+    }
+
+    /// Returns the pointers and spans of the values, in source order.
+    pub fn values(&self) -> impl Iterator<Item = (&JsonPointer, &Span)> {
+        self.values.iter()
+    }
+
+    /// Returns the pointers and spans of the object keys, in source order.
+    pub fn keys(&self) -> impl Iterator<Item = (&JsonPointer, &Span)> {
+        self.keys.iter()
+    }
+}
+
+/// A value parsed along with the tokens it spans in the source.
+pub(crate) struct Spanned<'a> {
+    json: Json<'a>,
+    node: SpanNode<'a>,
+}
+
+/// The source text of a value and of its key, along with the ones of its children.
+#[derive(Default)]
+struct SpanNode<'a> {
+    /// The first and last tokens of the value, unless it stands for the values of a repeated key
+    /// collected into an array.
+    tokens: Option<(&'a str, &'a str)>,
+    /// The key of the member holding the value, if any.
+    key: Option<&'a str>,
+    /// The children of the value with their reference tokens.
+    children: Vec<(String, SpanNode<'a>)>,
+}
+
+impl<'a> Syntax<'a> for Spanned<'a> {
+    type Elements = Vec<Spanned<'a>>;
+    type Members = Members<'a, Spanned<'a>>;
+
+    fn scalar(token: Lexeme<'a>, json: Json<'a>) -> Self {
+        let node = SpanNode {
+            tokens: Some((token.text, token.text)),
+            ..SpanNode::default()
+        };
+        Spanned { json, node }
+    }
+
+    fn push_element(elements: &mut Self::Elements, value: Self, _: Option<Lexeme<'a>>) {
+        elements.push(value);
+    }
+
+    fn array(open: Lexeme<'a>, elements: Self::Elements, close: Lexeme<'a>) -> Self {
+        let mut values = Vec::with_capacity(elements.len());
+        let mut children = Vec::with_capacity(elements.len());
+        for (i, element) in elements.into_iter().enumerate() {
+            values.push(element.json);
+            children.push((i.to_string(), element.node));
+        }
+        let node = SpanNode {
+            tokens: Some((open.text, close.text)),
+            key: None,
+            children,
+        };
+        Spanned {
+            json: values.into(),
+            node,
+        }
+    }
+
+    fn push_member(
+        members: &mut Self::Members,
+        member: Member<'a, Self>,
+        policy: DuplicateKeys,
+    ) -> bool {
+        let mut value = member.value;
+        value.node.key = Some(member.key.text);
+        members.insert(member.name, value, policy)
+    }
+
+    fn object(open: Lexeme<'a>, members: Self::Members, close: Lexeme<'a>) -> Self {
+        let mut map = IndexMap::with_capacity(members.map.len());
+        let mut children = Vec::with_capacity(members.map.len());
+        for (name, value) in members.map {
+            children.push((name.to_string(), value.node));
+            map.insert(name, value.json);
+        }
+        let node = SpanNode {
+            tokens: Some((open.text, close.text)),
+            key: None,
+            children,
+        };
+        Spanned {
+            json: Map(map).into(),
+            node,
+        }
+    }
+}
+
+impl Collect for Spanned<'_> {
+    fn collect(&mut self, value: Self, collected: bool) {
+        self.json.collect(value.json, collected);
+        if !collected {
+            let first = mem::take(&mut self.node);
+            self.node.children.push(("0".to_string(), first));
+        }
+        let index = self.node.children.len().to_string();
+        self.node.children.push((index, value.node));
+    }
+}
+
+/// Splits a value parsed from `source` into its `Json` value and the spans of its values.
+pub(crate) fn with_spans<'a>(value: Spanned<'a>, source: &'a str) -> (Json<'a>, Spans) {
+    let mut values = IndexMap::new();
+    let mut keys = IndexMap::new();
+    // Walks the tree depth first, with the children of a value pushed in reverse so that they
+    // are visited in order.
+    let mut stack = vec![(JsonPointer::new(), value.node)];
+    while let Some((pointer, node)) = stack.pop() {
+        let range =
+            |first: &str, last: &str| source.offset(first)..source.offset(last) + last.len();
+        if let Some((first, last)) = node.tokens {
+            values.insert(pointer.clone(), range(first, last));
+        }
+        if let Some(key) = node.key {
+            keys.insert(pointer.clone(), range(key, key));
+        }
+        for (segment, child) in node.children.into_iter().rev() {
+            stack.push((pointer.clone().child(segment), child));
+        }
+    }
+
+    // Locates the offsets in a single pass over the source.
+    let mut offsets = values
+        .values()
+        .chain(keys.values())
+        .flat_map(|range: &Range<usize>| [range.start, range.end])
+        .collect::<Vec<_>>();
+    offsets.sort_unstable();
+    offsets.dedup();
+    let mut location = Location {
+        offset: 0,
+        line: 1,
+        column: 1,
+    };
+    let locations = offsets
+        .iter()
+        .map(|&offset| {
+            for c in source[location.offset..offset].chars() {
+                if c == '\n' {
+                    location.line += 1;
+                    location.column = 1;
+                } else {
+                    location.column += 1;
+                }
+            }
+            location.offset = offset;
+            location
+        })
+        .collect::<Vec<_>>();
+    let locate = |range: Range<usize>| {
+        let location = |offset| locations[offsets.binary_search(&offset).unwrap()];
+        Span {
+            start: location(range.start),
+            end: location(range.end),
+        }
+    };
+
+    // Repeated keys may move values after the ones following them in the source.
+    let mut spans = Spans {
+        values: values.into_iter().map(|(p, r)| (p, locate(r))).collect(),
+        keys: keys.into_iter().map(|(p, r)| (p, locate(r))).collect(),
+    };
+    spans
+        .values
+        .sort_by(|_, a, _, b| a.start.offset.cmp(&b.start.offset));
+    spans
+        .keys
+        .sort_by(|_, a, _, b| a.start.offset.cmp(&b.start.offset));
+    (value.json, spans)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::JsonErrorKind;
+    use crate::options::{DuplicateKeys, ParserOptions};
+    use crate::parser::{parse_complete, parse_with_spans};
+
+    #[test]
+    fn test_spans() {
+        let input = "[\r\n  {\"a\" : -1.5e3, \"é\": \"ü\"},\r\n  [], null\r\n]";
+        let (json, spans) = parse_with_spans(input).unwrap();
+        assert_eq!(json, parse_complete(input).unwrap());
+        let text = |pointer| &input[spans.value(pointer).unwrap().range()];
+        assert_eq!(text(""), input);
+        assert_eq!(text("/0"), "{\"a\" : -1.5e3, \"é\": \"ü\"}");
+        assert_eq!(text("/0/a"), "-1.5e3");
+        assert_eq!(text("/0/é"), "\"ü\"");
+        assert_eq!(text("/1"), "[]");
+        assert_eq!(text("/2"), "null");
+        assert_eq!(&input[spans.key("/0/é").unwrap().range()], "\"é\"");
+
+        let span = spans.value("/0/é").unwrap();
+        assert_eq!((span.start.line, span.start.column), (2, 23));
+        assert_eq!((span.end.line, span.end.column), (2, 26));
+        let span = spans.value("").unwrap();
+        assert_eq!((span.end.line, span.end.column), (4, 2));
+
+        assert_eq!(spans.key("/0"), None);
+        assert_eq!(spans.value("/3"), None);
+        assert_eq!(spans.value("x"), None);
+        let pointers = spans
+            .values()
+            .map(|(p, _)| p.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(pointers, ["", "/0", "/0/a", "/0/é", "/1", "/2"]);
+        let pointers = spans.keys().map(|(p, _)| p.to_string()).collect::<Vec<_>>();
+        assert_eq!(pointers, ["/0/a", "/0/é"]);
+    }
+
+    #[test]
+    fn test_spans_with_options() {
+        let input = "// c\n{'a': {b: 1}, a: [2], c: 3, a: 4,}";
+        let parse = |policy| {
+            let options = ParserOptions::new().json5(true).duplicate_keys(policy);
+            let parser = options.build();
+            let (json, spans) = parser.parse_with_spans(input).unwrap();
+            assert_eq!(json, parser.parse_complete(input).unwrap());
+            let pointers = spans
+                .values()
+                .map(|(p, _)| p.to_string())
+                .collect::<Vec<_>>();
+            let text = |pointer| &input[spans.value(pointer).unwrap().range()];
+            assert_eq!(text(""), &input[5..]);
+            (pointers, text("/a").to_string())
+        };
+
+        let (pointers, a) = parse(DuplicateKeys::FirstWins);
+        assert_eq!(pointers, ["", "/a", "/a/b", "/c"]);
+        assert_eq!(a, "{b: 1}");
+        let (pointers, a) = parse(DuplicateKeys::LastWins);
+        assert_eq!(pointers, ["", "/c", "/a"]);
+        assert_eq!(a, "4");
+
+        let options = ParserOptions::new()
+            .json5(true)
+            .duplicate_keys(DuplicateKeys::Collect);
+        let parser = options.build();
+        let (json, spans) = parser.parse_with_spans(input).unwrap();
+        assert_eq!(json, parser.parse_complete(input).unwrap());
+        let text = |pointer| &input[spans.value(pointer).unwrap().range()];
+        assert_eq!(text("/a/0"), "{b: 1}");
+        assert_eq!(text("/a/1/0"), "2");
+        assert_eq!(text("/a/2"), "4");
+        assert_eq!(&input[spans.key("/a/0").unwrap().range()], "'a'");
+        assert_eq!(spans.value("/a"), None);
+
+        let options = ParserOptions::new()
+            .json5(true)
+            .duplicate_keys(DuplicateKeys::Error);
+        let err = options.build().parse_with_spans(input).unwrap_err();
+        assert_eq!(err.kind(), JsonErrorKind::DuplicateKey);
+        assert!(parse_with_spans("[1] x").is_err());
+    }
+}
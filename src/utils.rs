@@ -1,27 +1,33 @@
+use crate::error::JsonParseError;
+
 use aho_corasick::AhoCorasick;
 use arrayvec::ArrayVec;
 use debug_unreachable::debug_unreachable;
-use nom::character::complete::multispace0;
-use nom::combinator::{iterator, map, opt, ParserIterator};
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while1, take_while_m_n};
+use nom::character::complete::{multispace0, multispace1};
+use nom::combinator::{iterator, map, opt, recognize, ParserIterator};
 use nom::error::{ErrorKind, ParseError};
-use nom::sequence::{delimited, tuple};
+use nom::multi::many0_count;
+use nom::sequence::{delimited, pair};
 use nom::{IResult, Offset, Slice};
 use once_cell::sync::Lazy;
 use std::borrow::Cow;
 use std::cell::Cell;
 use std::fmt::{Error, Formatter, Write};
 use std::ops::{Range, RangeTo};
+use std::str::Chars;
 
 pub(crate) const HIGH_SURROGATES: Range<u16> = 0xd800..0xdc00;
 pub(crate) const LOW_SURROGATES: Range<u16> = 0xdc00..0xe000;
 
-pub(crate) fn escape(input: &str) -> Cow<str> {
+pub(crate) fn escape(input: &str) -> Cow<'_, str> {
     static PATTERNS: &[&str] = &[
         "\"", "\\", "\x00", "\x01", "\x02", "\x03", "\x04", "\x05", "\x06", "\x07", "\x08", "\x09",
         "\x0a", "\x0b", "\x0c", "\x0d", "\x0e", "\x0f", "\x10", "\x11", "\x12", "\x13", "\x14",
         "\x15", "\x16", "\x17", "\x18", "\x19", "\x1a", "\x1b", "\x1c", "\x1d", "\x1e", "\x1f",
     ];
-    static REPLACEMENTS: &'static [&'static str] = &[
+    static REPLACEMENTS: &[&str] = &[
         r#"\""#,
         r#"\\"#,
         r#"\u0000"#,
@@ -70,12 +76,11 @@ pub(crate) fn escape(input: &str) -> Cow<str> {
     res
 }
 
-pub(crate) fn unescape(input: &str) -> Cow<str> {
-    static PATTERNS: &'static [&'static str] = &[
-        r#"\""#, r"\\", r"\/", r"\b", r"\f", r"\n", r"\r", r"\t", r"\u",
+pub(crate) fn unescape(input: &str) -> Cow<'_, str> {
+    static PATTERNS: &[&str] = &[
+        r#"\""#, r"\\", r"\/", r"\b", r"\f", r"\n", r"\r", r"\t", r"\'", r"\u",
     ];
-    static REPLACEMENTS: &'static [&'static str] =
-        &["\"", "\\", "/", "\x08", "\x0c", "\x0a", "\x0d", "\x09"];
+    static REPLACEMENTS: &[&str] = &["\"", "\\", "/", "\x08", "\x0c", "\x0a", "\x0d", "\x09", "'"];
     static AC: Lazy<AhoCorasick> = Lazy::new(|| AhoCorasick::new_auto_configured(PATTERNS));
 
     let mut res = Cow::default();
@@ -99,6 +104,8 @@ pub(crate) fn unescape(input: &str) -> Cow<str> {
                 vec.clear();
             }
         } else {
+            // `debug_unreachable!` checks the `ndebug` cfg, which Cargo does not know about
+            #[allow(unexpected_cfgs)]
             unsafe {
                 debug_unreachable!();
             }
@@ -108,6 +115,58 @@ pub(crate) fn unescape(input: &str) -> Cow<str> {
     res
 }
 
+/// Unescapes the content of a JSON5 string or identifier. Besides the escape sequences of JSON,
+/// JSON5 has `\v`, `\0`, `\xHH`, escaped line terminators that are removed, and escaped characters
+/// that stand for themselves, such as `\'`.
+pub(crate) fn unescape_json5(input: &str) -> Cow<'_, str> {
+    if !input.contains('\\') {
+        return Cow::Borrowed(input);
+    }
+    let hex = |chars: &mut Chars, len: usize| {
+        let digits = chars.as_str().get(..len).unwrap_or("");
+        chars.nth(len - 1);
+        u32::from_str_radix(digits, 16).unwrap_or(0xfffd)
+    };
+    let mut res = String::with_capacity(input.len());
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('b') => res.push('\x08'),
+            Some('f') => res.push('\x0c'),
+            Some('n') => res.push('\n'),
+            Some('r') => res.push('\r'),
+            Some('t') => res.push('\t'),
+            Some('v') => res.push('\x0b'),
+            Some('0') => res.push('\0'),
+            Some('x') => res.push(char::from_u32(hex(&mut chars, 2)).unwrap()),
+            Some('u') => {
+                let mut units: ArrayVec<[u16; 2]> = ArrayVec::new();
+                units.push(hex(&mut chars, 4) as u16);
+                if HIGH_SURROGATES.contains(&units[0]) && chars.as_str().starts_with("\\u") {
+                    chars.nth(1);
+                    units.push(hex(&mut chars, 4) as u16);
+                }
+                res.extend(
+                    std::char::decode_utf16(units)
+                        .map(|c| c.unwrap_or(std::char::REPLACEMENT_CHARACTER)),
+                );
+            }
+            Some('\r') => {
+                if chars.as_str().starts_with('\n') {
+                    chars.next();
+                }
+            }
+            Some('\n') | Some('\u{2028}') | Some('\u{2029}') | None => {}
+            Some(c) => res.push(c),
+        }
+    }
+    Cow::Owned(res)
+}
+
 pub(crate) struct PadAdapter<'a, 'b: 'a> {
     fmt: &'a mut Formatter<'b>,
     on_newline: bool,
@@ -160,6 +219,75 @@ where
     delimited(multispace0, wrapped, multispace0)
 }
 
+/// The whitespace skipped between tokens.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub(crate) enum Whitespace {
+    /// Spaces, tabs and line breaks, as in JSON.
+    Json,
+    /// The whitespace of JSON, and `//` and `/* */` comments.
+    Comments,
+    /// The whitespace of JSON5: comments, and Unicode space separators and line terminators as
+    /// well.
+    Json5,
+}
+
+/// Skips whitespace of the given kind and returns it.
+pub(crate) fn space0<'a, E: JsonParseError<'a>>(
+    ws: Whitespace,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, E> {
+    move |input| match ws {
+        Whitespace::Json => multispace0(input),
+        Whitespace::Comments => recognize(many0_count(alt((multispace1, comment))))(input),
+        Whitespace::Json5 => {
+            let is_space = |c: char| (c.is_whitespace() && c != '\u{85}') || c == '\u{feff}';
+            recognize(many0_count(alt((take_while1(is_space), comment))))(input)
+        }
+    }
+}
+
+/// Skips whitespace of the given kind up to and including the end of the line, and returns it.
+pub(crate) fn line_space0<'a, E: JsonParseError<'a>>(
+    ws: Whitespace,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, E> {
+    let is_space = move |c: char| match ws {
+        Whitespace::Json | Whitespace::Comments => c == ' ' || c == '\t',
+        Whitespace::Json5 => {
+            !is_line_terminator(c) && ((c.is_whitespace() && c != '\u{85}') || c == '\u{feff}')
+        }
+    };
+    let trivia = move |input| match ws {
+        Whitespace::Json => Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Tag))),
+        _ => comment(input),
+    };
+    let line_end = |input| alt((tag("\r\n"), take_while_m_n(1, 1, is_line_terminator)))(input);
+    recognize(pair(
+        many0_count(alt((take_while1(is_space), trivia))),
+        opt(line_end),
+    ))
+}
+
+fn is_line_terminator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+/// Matches a `//` comment up to the end of the line, or a `/* */` comment.
+fn comment<'a, E: JsonParseError<'a>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    if let Some(rest) = input.strip_prefix("//") {
+        let end = rest.find(is_line_terminator).unwrap_or(rest.len());
+        Ok((&rest[end..], &input[..end + 2]))
+    } else if let Some(rest) = input.strip_prefix("/*") {
+        match rest.find("*/") {
+            Some(end) => Ok((&rest[end + 2..], &input[..end + 4])),
+            None => Err(nom::Err::Failure(E::from_expected(
+                &rest[rest.len()..],
+                "the end of the comment",
+            ))),
+        }
+    } else {
+        Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Tag)))
+    }
+}
+
 pub(crate) fn into<I, O: Into<R>, R, E: ParseError<I>, F>(from: F) -> impl Fn(I) -> IResult<I, R, E>
 where
     F: Fn(I) -> IResult<I, O, E>,
@@ -174,22 +302,44 @@ where
     into(from)(input)
 }
 
-pub(crate) fn delimited_list<I: Clone, O, O1, E: ParseError<I>, F, G>(
+/// The result of parsing an item of a list along with the separator following it, if any.
+type ItemResult<I, O, O1, E> = IResult<I, (O, Option<O1>), E>;
+
+/// Iterates over the items of a list separated by `sep`, along with the separator following each
+/// item. With a `trailing` parser of the end of the list, the last item may be followed by a
+/// separator.
+pub(crate) fn delimited_list<I: Clone, O, O1, O2, E: ParseError<I>, F, G, H>(
     input: I,
     item: F,
     sep: G,
-) -> ParserIterator<I, E, impl Fn(I) -> IResult<I, O, E>>
+    trailing: Option<H>,
+) -> ParserIterator<I, E, impl Fn(I) -> ItemResult<I, O, O1, E>>
 where
     F: Fn(I) -> IResult<I, O, E>,
     G: Fn(I) -> IResult<I, O1, E>,
+    H: Fn(I) -> IResult<I, O2, E>,
 {
     let parsing = Cell::new(true);
-    let parser = tuple((item, map(opt(sep), |opt| opt.is_some())));
-    iterator(input, move |input| {
+    let first = Cell::new(true);
+    iterator(input, move |input: I| {
         if parsing.get() {
-            let (input, (item, has_sep)) = parser(input)?;
-            parsing.set(has_sep);
-            Ok((input, item))
+            // The first item may be missing in an empty list, but an item following a separator
+            // is mandatory unless the separator is a trailing one followed by the end of the list.
+            let first = first.replace(false);
+            let (input, item) = match item(input.clone()) {
+                Err(nom::Err::Error(err)) if !first => match &trailing {
+                    Some(end) if end(input).is_ok() => return Err(nom::Err::Error(err)),
+                    _ => return Err(nom::Err::Failure(err)),
+                },
+                res => res?,
+            };
+            let (input, sep) = match sep(input.clone()) {
+                Ok((input, sep)) => (input, Some(sep)),
+                Err(nom::Err::Error(_)) => (input, None),
+                Err(err) => return Err(err),
+            };
+            parsing.set(sep.is_some());
+            Ok((input, (item, sep)))
         } else {
             Err(nom::Err::Error(E::from_error_kind(
                 input,
@@ -270,8 +420,9 @@ mod test {
             input,
             terminated(tag("["), multispace0),
             move |input| {
-                delimited_list(input, &elem, terminated(char(','), multispace0))
-                    .process(|it| it.collect())
+                let end = None::<fn(&'a str) -> IResult<&'a str, &'a str, E>>;
+                delimited_list(input, &elem, terminated(char(','), multispace0), end)
+                    .process(|it| it.map(|(elem, _)| elem).collect())
             },
             preceded(multispace0, tag("]")),
         )
@@ -282,6 +433,21 @@ mod test {
         assert_eq!(parse_vector::<E, _, _>("[]", double), Ok(("", Vec::new())));
     }
 
+    #[test]
+    fn test_delimited_list_trailing() {
+        let parse = |input, trailing: bool| {
+            let end = trailing.then(|| char(']'));
+            delimited_list::<_, _, _, _, E, _, _, _>(input, double, char(','), end)
+                .process(|it| it.map(|(item, _)| item).collect::<Vec<_>>())
+        };
+        assert_eq!(parse("1,2,]", true), Ok(("]", vec![1.0, 2.0])));
+        assert_eq!(parse("1,2]", true), Ok(("]", vec![1.0, 2.0])));
+        assert!(parse("1,2,", true).is_err());
+        assert!(parse("1,,]", true).is_err());
+        assert!(parse("1,x]", true).is_err());
+        assert!(parse("1,2,]", false).is_err());
+    }
+
     proptest! {
         #[test]
         fn test_unescape_random(s in r#"[^\pC\\]*"#) {
@@ -302,7 +468,7 @@ mod test {
         }
 
         #[test]
-        fn test_delimited_list(v in vec(POSITIVE | NEGATIVE | ZERO | INFINITE, 0..=1000 as usize)) {
+        fn test_delimited_list(v in vec(POSITIVE | NEGATIVE | ZERO | INFINITE, 0..=1000usize)) {
             let input = format!("{:?}", v);
             prop_assert_eq!(parse_vector::<E, _, _>(&input, double), Ok(("", v)));
         }